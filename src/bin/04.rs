@@ -1,21 +1,15 @@
+use advent_of_code::helpers::{interval::Interval, parsing::Pattern};
+
 /// A zone the elves have to clean
-#[derive(Debug, Clone, Copy, PartialEq)]
-struct CleaningZone {
-    min: u32,
-    max: u32,
-}
+type CleaningZone = Interval;
 
 /// Parse a zone out of a string of the format "12-34"
 fn parse_zone(input: &str) -> CleaningZone {
-    let parsed_nums = input
-        .split('-')
-        .map(|val| val.parse::<u32>().expect("Section is not an integer"))
-        .collect::<Vec<u32>>();
-
-    CleaningZone {
-        min: *parsed_nums.first().unwrap(),
-        max: *parsed_nums.last().unwrap(),
-    }
+    let [min, max] = Pattern::new("{}-{}")
+        .parse_array(input)
+        .expect("Section is not an integer");
+
+    Interval::new(min, max).expect("Zone ends before it starts")
 }
 
 fn parse_puzzle_line(input: &str) -> (CleaningZone, CleaningZone) {
@@ -28,13 +22,12 @@ fn parse_puzzle_line(input: &str) -> (CleaningZone, CleaningZone) {
 
 /// Return whether one zone fully contains the other
 fn fully_contained(left: CleaningZone, right: CleaningZone) -> bool {
-    (left.min <= right.min && left.max >= right.max)
-        || (right.min <= left.min && right.max >= left.max)
+    left.contains_interval(&right) || right.contains_interval(&left)
 }
 
 /// Return whether the zones intersect at all
 fn any_overlap(left: CleaningZone, right: CleaningZone) -> bool {
-    !((left.max < right.min) || (left.min > right.max))
+    left.overlaps(&right)
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
@@ -69,15 +62,15 @@ mod tests {
     use rstest::rstest;
 
     #[rstest]
-    #[case("2-3", CleaningZone {min: 2, max: 3})]
+    #[case("2-3", CleaningZone { start: 2, end: 3 })]
     fn test_parse_zone(#[case] input: &str, #[case] zone: CleaningZone) {
         assert_eq!(parse_zone(input), zone);
     }
 
     #[rstest]
-    #[case(CleaningZone {min: 2, max: 8}, CleaningZone { min: 3, max: 7 }, true)]
-    #[case(CleaningZone {min: 6, max: 6}, CleaningZone { min: 4, max: 6 }, true)]
-    #[case(CleaningZone {min: 2, max: 3}, CleaningZone { min: 4, max: 5 }, false)]
+    #[case(CleaningZone { start: 2, end: 8 }, CleaningZone { start: 3, end: 7 }, true)]
+    #[case(CleaningZone { start: 6, end: 6 }, CleaningZone { start: 4, end: 6 }, true)]
+    #[case(CleaningZone { start: 2, end: 3 }, CleaningZone { start: 4, end: 5 }, false)]
     fn test_fully_contained(
         #[case] left_zone: CleaningZone,
         #[case] right_zone: CleaningZone,
@@ -87,9 +80,9 @@ mod tests {
     }
 
     #[rstest]
-    #[case(CleaningZone {min: 2, max: 8}, CleaningZone { min: 3, max: 7 }, true)]
-    #[case(CleaningZone {min: 5, max: 7}, CleaningZone { min: 7, max: 9 }, true)]
-    #[case(CleaningZone {min: 2, max: 4}, CleaningZone { min: 6, max: 8 }, false)]
+    #[case(CleaningZone { start: 2, end: 8 }, CleaningZone { start: 3, end: 7 }, true)]
+    #[case(CleaningZone { start: 5, end: 7 }, CleaningZone { start: 7, end: 9 }, true)]
+    #[case(CleaningZone { start: 2, end: 4 }, CleaningZone { start: 6, end: 8 }, false)]
     fn test_any_overlap(
         #[case] left_zone: CleaningZone,
         #[case] right_zone: CleaningZone,
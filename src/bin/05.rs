@@ -1,160 +1,912 @@
-use std::collections::HashMap;
+use std::{fmt, fs, path::Path, thread, time::Duration};
+
+use advent_of_code::helpers::{
+    interval::Interval,
+    parsing::{ParseError, Pattern},
+    search::bfs,
+};
+
+/// A crate's ID, usually a single letter
+type Crate = String;
 
 /// A yard of crates in stacks, with each stack given a unique number
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone)]
 struct CrateYard {
-    crates: HashMap<u32, Vec<char>>,
+    /// Stack labels, in the order the stacks are drawn
+    labels: Vec<u32>,
+    /// The crates in each stack from the bottom up, in the same order as the labels
+    stacks: Vec<Vec<Crate>>,
+    /// Every move applied so far, oldest first
+    log: Vec<LoggedMove>,
+    /// Moves taken back with `undo`, the most recently undone last, until a new move is applied
+    undone: Vec<LoggedMove>,
 }
 
-/// An instruction to move crates from one stack to another
+/// An applied instruction, with what it needs to be undone and redone
+#[derive(Debug, Clone, PartialEq)]
+struct LoggedMove {
+    instruction: CrateInstruction,
+    /// Positions in `stacks` of the stacks the crates went from and to
+    from: usize,
+    to: usize,
+    /// The crates taken off the top of the `from` stack, bottom to top as they stood there
+    lifted: Vec<Crate>,
+    /// The same crates as the crane set them down on the `to` stack
+    landed: Vec<Crate>,
+}
+
+/// How one stack differs between two yards, above the crates they have in common
 #[derive(Debug, PartialEq)]
+struct StackDiff {
+    label: u32,
+    /// Crates only the first yard has
+    removed: Vec<Crate>,
+    /// Crates only the second yard has
+    added: Vec<Crate>,
+}
+
+/// An instruction to move crates from one stack to another
+#[derive(Debug, Clone, PartialEq)]
 struct CrateInstruction {
     quantity: u32,
     from: u32,
     to: u32,
 }
 
+/// What's wrong with a yard drawing or a crate instruction
+#[derive(Debug, Clone, PartialEq)]
+enum YardErrorKind {
+    /// There's no blank line between the drawing and the instructions
+    MissingInstructions,
+    /// A token in the label row isn't a stack number, or repeats one
+    MalformedLabel(String),
+    /// A drawing row, by 1-based line, isn't `[X]` cells and gaps, each cell under exactly one label
+    RaggedRow(usize),
+    MalformedInstruction(ParseError),
+    UnknownStack(u32),
+    TooManyCrates {
+        stack: u32,
+        requested: u32,
+        available: usize,
+    },
+}
+
+/// An invalid yard, with the 1-based index of the instruction at fault if there is one
+#[derive(Debug, PartialEq)]
+struct YardError {
+    instruction: Option<usize>,
+    kind: YardErrorKind,
+}
+
+impl YardError {
+    fn new(kind: YardErrorKind) -> YardError {
+        YardError {
+            instruction: None,
+            kind,
+        }
+    }
+
+    /// Attribute an error to the instruction at a 1-based index
+    fn at_instruction(self, index: usize) -> YardError {
+        YardError {
+            instruction: Some(index),
+            ..self
+        }
+    }
+
+    /// Place a malformed instruction on its 1-based line of the whole input
+    fn on_line(self, line: usize) -> YardError {
+        match self.kind {
+            YardErrorKind::MalformedInstruction(e) => YardError {
+                kind: YardErrorKind::MalformedInstruction(e.on_line(line)),
+                ..self
+            },
+            _ => self,
+        }
+    }
+}
+
+impl fmt::Display for YardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(index) = self.instruction {
+            write!(f, "instruction {}: ", index)?;
+        }
+        match &self.kind {
+            YardErrorKind::MissingInstructions => {
+                write!(f, "expected a blank line between the yard and the moves")
+            }
+            YardErrorKind::MalformedLabel(label) => write!(f, "invalid stack label \"{}\"", label),
+            YardErrorKind::RaggedRow(line) => {
+                write!(f, "line {} doesn't line up with the stack labels", line)
+            }
+            YardErrorKind::MalformedInstruction(e) => write!(f, "{}", e),
+            YardErrorKind::UnknownStack(stack) => write!(f, "there is no stack {}", stack),
+            YardErrorKind::TooManyCrates {
+                stack,
+                requested,
+                available,
+            } => write!(
+                f,
+                "cannot take {} crates from stack {}, which holds {}",
+                requested, stack, available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for YardError {}
+
 impl CrateYard {
-    /// Apply a single crate instruction for the CrateMover 9000
-    fn apply_instruction_crane9000(&mut self, instruction: CrateInstruction) {
-        (0..instruction.quantity).for_each(|_| {
-            let taken_crate = self
-                .crates
-                .get_mut(&instruction.from)
-                .expect("Cannot move from stack.")
-                .pop()
-                .expect("Cannot take from an empty stack.");
-            self.crates
-                .get_mut(&instruction.to)
-                .expect("Cannot move to stack.")
-                .push(taken_crate);
-        })
+    fn new(labels: Vec<u32>, stacks: Vec<Vec<Crate>>) -> Self {
+        CrateYard {
+            labels,
+            stacks,
+            log: vec![],
+            undone: vec![],
+        }
+    }
+
+    /// Where the stack with the given label sits in `stacks`
+    fn position(&self, label: u32) -> Option<usize> {
+        self.labels.iter().position(|&l| l == label)
+    }
+
+    /// The crates in the stack with the given label, or none if there's no such stack
+    fn stack(&self, label: u32) -> &[Crate] {
+        self.position(label)
+            .map_or(&[], |position| &self.stacks[position])
+    }
+
+    /// Make sure a move can be carried out before touching any stack,
+    /// returning the positions of the stacks involved
+    fn check(&self, instruction: &CrateInstruction) -> Result<(usize, usize), YardError> {
+        let position = |stack| {
+            self.position(stack)
+                .ok_or_else(|| YardError::new(YardErrorKind::UnknownStack(stack)))
+        };
+        let (from, to) = (position(instruction.from)?, position(instruction.to)?);
+
+        let available = self.stacks[from].len();
+        if available < instruction.quantity as usize {
+            return Err(YardError::new(YardErrorKind::TooManyCrates {
+                stack: instruction.from,
+                requested: instruction.quantity,
+                available,
+            }));
+        }
+        Ok((from, to))
+    }
+
+    /// Apply a single crate instruction with the given crane
+    fn apply(
+        &mut self,
+        crane: &impl Crane,
+        instruction: &CrateInstruction,
+    ) -> Result<(), YardError> {
+        let (from, to) = self.check(instruction)?;
+        let from_stack = &mut self.stacks[from];
+        let lifted = from_stack.split_off(from_stack.len() - instruction.quantity as usize);
+        let landed = crane.land(lifted.clone());
+        self.stacks[to].extend(landed.iter().cloned());
+        self.log.push(LoggedMove {
+            instruction: instruction.clone(),
+            from,
+            to,
+            lifted,
+            landed,
+        });
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// Take back up to `moves` of the latest moves, returning their instructions latest first
+    fn undo(&mut self, moves: usize) -> Vec<CrateInstruction> {
+        let mut instructions = vec![];
+        for _ in 0..moves {
+            let Some(logged) = self.log.pop() else { break };
+            let to_stack = &mut self.stacks[logged.to];
+            to_stack.truncate(to_stack.len() - logged.landed.len());
+            self.stacks[logged.from].extend(logged.lifted.iter().cloned());
+            instructions.push(logged.instruction.clone());
+            self.undone.push(logged);
+        }
+        instructions
     }
 
-    /// Apply a single crate instruction for the CrateMover 9001
-    fn apply_instruction_crane9001(&mut self, instruction: CrateInstruction) {
-        let from_stack = self
-            .crates
-            .get_mut(&instruction.from)
-            .expect("Cannot move from stack");
-        let stack_len = from_stack.len();
-        let taken_crates = from_stack.split_off(stack_len - instruction.quantity as usize);
-        self.crates
-            .get_mut(&instruction.to)
-            .expect("Cannot move to stack")
-            .extend(taken_crates);
+    /// Make up to `moves` of the most recently undone moves again, returning their instructions
+    /// in the order they're redone
+    fn redo(&mut self, moves: usize) -> Vec<CrateInstruction> {
+        let mut instructions = vec![];
+        for _ in 0..moves {
+            let Some(logged) = self.undone.pop() else {
+                break;
+            };
+            let from_stack = &mut self.stacks[logged.from];
+            from_stack.truncate(from_stack.len() - logged.lifted.len());
+            self.stacks[logged.to].extend(logged.landed.iter().cloned());
+            instructions.push(logged.instruction.clone());
+            self.log.push(logged);
+        }
+        instructions
+    }
+
+    /// Every move in the history, whether it's currently made or undone
+    fn history_len(&self) -> usize {
+        self.log.len() + self.undone.len()
+    }
+
+    /// The yard as it stood after the first `step` moves of the history, if it has that many
+    fn at_step(&self, step: usize) -> Option<CrateYard> {
+        if step > self.history_len() {
+            return None;
+        }
+        let mut yard = self.clone();
+        yard.undo(self.log.len().saturating_sub(step));
+        yard.redo(step.saturating_sub(self.log.len()));
+        Some(yard)
+    }
+
+    /// The stacks that differ between this yard and `other`, in this yard's label order
+    /// followed by any stacks only `other` has
+    fn diff(&self, other: &CrateYard) -> Vec<StackDiff> {
+        let only_other = other.labels.iter().filter(|l| !self.labels.contains(l));
+
+        self.labels
+            .iter()
+            .chain(only_other)
+            .filter_map(|&label| {
+                let (before, after) = (self.stack(label), other.stack(label));
+                let shared = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+                (shared < before.len().max(after.len())).then(|| StackDiff {
+                    label,
+                    removed: before[shared..].to_vec(),
+                    added: after[shared..].to_vec(),
+                })
+            })
+            .collect()
     }
 
     /// Get the readout of the top crates from each stack
     fn top_readout(&self) -> String {
-        let mut entries = self.crates.iter().collect::<Vec<(&u32, &Vec<char>)>>();
-        entries.sort_by(|(index_a, _), (index_b, _)| index_a.cmp(index_b));
+        self.stacks
+            .iter()
+            .filter_map(|stack| stack.last())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Draw the yard the way the puzzle input does, with the stack labels underneath. Each column
+/// is as wide as its widest crate or label, with crates and labels centered in it.
+impl fmt::Display for CrateYard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths = self
+            .labels
+            .iter()
+            .zip(&self.stacks)
+            .map(|(label, stack)| {
+                let widest = stack.iter().map(|c| c.chars().count() + 2).max();
+                widest.unwrap_or(0).max(3).max(label.to_string().len())
+            })
+            .collect::<Vec<_>>();
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+
+        for level in (0..height).rev() {
+            let row = self
+                .stacks
+                .iter()
+                .zip(&widths)
+                .map(|(stack, &width)| match stack.get(level) {
+                    Some(c) => format!("{:^width$}", format!("[{}]", c)),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", row.join(" "))?;
+        }
 
-        entries
+        let label_row = self
+            .labels
             .iter()
-            .filter_map(|(_, stack)| stack.iter().last())
-            .collect::<String>()
+            .zip(&widths)
+            .map(|(label, &width)| format!("{:^width$}", label))
+            .collect::<Vec<_>>();
+        write!(f, "{}", label_row.join(" "))
     }
 }
 
-impl From<Vec<&str>> for CrateYard {
-    /// Create from multiline text definition
-    fn from(lines: Vec<&str>) -> Self {
-        let mut reader = lines.iter().rev();
-        let stack_labels = reader
-            .next()
-            .expect("No indices row")
-            .split_whitespace()
-            .map(|i| i.parse::<u32>().expect("Index is not a valid integer"))
-            .collect::<Vec<u32>>();
+/// Yards are equal when their stacks are, whatever moves got them there
+impl PartialEq for CrateYard {
+    fn eq(&self, other: &Self) -> bool {
+        self.labels == other.labels && self.stacks == other.stacks
+    }
+}
 
-        let mut stack_map = HashMap::<u32, Vec<char>>::new();
+/// Show the crates as `stack 2: -[D] +[C][M]`, leaving out whichever side is empty
+impl fmt::Display for StackDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let crates = |sign, crates: &[Crate]| {
+            let drawn = crates
+                .iter()
+                .map(|c| format!("[{}]", c))
+                .collect::<String>();
+            (!crates.is_empty()).then(|| format!("{}{}", sign, drawn))
+        };
+        let changes = [crates('-', &self.removed), crates('+', &self.added)];
+        let changes = changes.into_iter().flatten().collect::<Vec<_>>();
+        write!(f, "stack {}: {}", self.label, changes.join(" "))
+    }
+}
 
-        stack_labels.iter().for_each(|label| {
-            stack_map.insert(*label, Vec::new());
-        });
+impl fmt::Display for CrateInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.quantity, self.from, self.to
+        )
+    }
+}
+
+/// How a crane model carries crates from one stack to another
+trait Crane {
+    /// Rearrange crates lifted off a stack, bottom to top, into the order they land in
+    fn land<T>(&self, lifted: Vec<T>) -> Vec<T>;
+}
+
+/// Moves crates one at a time, so they land in reverse order
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn land<T>(&self, mut lifted: Vec<T>) -> Vec<T> {
+        lifted.reverse();
+        lifted
+    }
+}
+
+/// Moves all the crates at once, keeping their order
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn land<T>(&self, lifted: Vec<T>) -> Vec<T> {
+        lifted
+    }
+}
+
+/// Moves at most `capacity` crates at a time, taking them off the top in batches
+struct BatchCrane {
+    /// At least 1
+    capacity: usize,
+}
 
-        fn index_location(index: usize) -> usize {
-            (index * 4) + 1
+impl Crane for BatchCrane {
+    fn land<T>(&self, mut lifted: Vec<T>) -> Vec<T> {
+        assert!(self.capacity > 0, "a crane has to carry at least one crate");
+        let mut landed = Vec::with_capacity(lifted.len());
+        while !lifted.is_empty() {
+            let batch = lifted.split_off(lifted.len().saturating_sub(self.capacity));
+            landed.extend(batch);
         }
+        landed
+    }
+}
 
-        reader.for_each(|line| {
-            stack_labels.iter().enumerate().for_each(|(index, label)| {
-                if let Some(c) = line.chars().nth(index_location(index)) {
-                    if c != ' ' {
-                        stack_map.get_mut(label).unwrap().push(c)
-                    }
-                }
-            })
-        });
+/// A crane model picked at run time
+enum CraneModel {
+    Mover9000,
+    Mover9001,
+    Batch(usize),
+}
+
+impl Crane for CraneModel {
+    fn land<T>(&self, lifted: Vec<T>) -> Vec<T> {
+        match self {
+            CraneModel::Mover9000 => CrateMover9000.land(lifted),
+            CraneModel::Mover9001 => CrateMover9001.land(lifted),
+            CraneModel::Batch(capacity) => BatchCrane {
+                capacity: *capacity,
+            }
+            .land(lifted),
+        }
+    }
+}
 
-        CrateYard { crates: stack_map }
+/// The space-separated tokens of a line, with the character columns each one covers
+fn tokens(line: &str) -> Vec<(Interval, &str)> {
+    let mut tokens = vec![];
+    let mut column = 0;
+    for token in line.split(' ') {
+        let width = token.chars().count() as i64;
+        if let Some(columns) = Interval::new(column, column + width - 1) {
+            tokens.push((columns, token));
+        }
+        column += width + 1;
     }
+    tokens
 }
 
-impl From<&str> for CrateInstruction {
+impl TryFrom<Vec<&str>> for CrateYard {
+    type Error = YardError;
+
+    /// Create from multiline text definition. A crate belongs to the stack whose label
+    /// shares a column with it, so columns can be any width and crates any length.
+    fn try_from(lines: Vec<&str>) -> Result<Self, YardError> {
+        let (label_row, drawing) = lines
+            .split_last()
+            .ok_or_else(|| YardError::new(YardErrorKind::MalformedLabel(String::new())))?;
+
+        let mut labels = Vec::<u32>::new();
+        let mut columns = Vec::<Interval>::new();
+        for (span, token) in tokens(label_row) {
+            let label = token
+                .parse::<u32>()
+                .ok()
+                .filter(|label| !labels.contains(label))
+                .ok_or_else(|| YardError::new(YardErrorKind::MalformedLabel(token.to_string())))?;
+            labels.push(label);
+            columns.push(span);
+        }
+        if labels.is_empty() {
+            return Err(YardError::new(YardErrorKind::MalformedLabel(
+                label_row.to_string(),
+            )));
+        }
+
+        let mut stacks = vec![Vec::new(); labels.len()];
+
+        for (line, row) in drawing.iter().enumerate().rev() {
+            let ragged = || YardError::new(YardErrorKind::RaggedRow(line + 1));
+            let mut filled = vec![false; labels.len()];
+
+            for (span, token) in tokens(row) {
+                let id = token
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .filter(|id| !id.is_empty() && !id.contains(['[', ']']))
+                    .ok_or_else(ragged)?;
+
+                let mut under = columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| c.overlaps(&span));
+                let stack = match (under.next(), under.next()) {
+                    (Some((stack, _)), None) if !filled[stack] => stack,
+                    _ => return Err(ragged()),
+                };
+                filled[stack] = true;
+                stacks[stack].push(id.to_string());
+            }
+        }
+
+        Ok(CrateYard::new(labels, stacks))
+    }
+}
+
+impl TryFrom<&str> for CrateInstruction {
+    type Error = YardError;
+
     /// Create from a line definition
-    fn from(line: &str) -> Self {
-        let mut reader = line.split_whitespace();
-        reader.next();
-        let quantity = reader
-            .next()
-            .expect("No quantity")
-            .parse::<u32>()
-            .expect("quantity is not an integer");
-        reader.next();
-        let from = reader
-            .next()
-            .expect("No from")
-            .parse::<u32>()
-            .expect("from is not an integer");
-        reader.next();
-        let to = reader
-            .next()
-            .expect("No to")
-            .parse::<u32>()
-            .expect("to is not an integer");
-
-        CrateInstruction { quantity, from, to }
+    fn try_from(line: &str) -> Result<Self, YardError> {
+        let [quantity, from, to] = Pattern::new("move {} from {} to {}")
+            .parse_array::<u32, 3>(line)
+            .map_err(|e| YardError::new(YardErrorKind::MalformedInstruction(e)))?;
+
+        Ok(CrateInstruction { quantity, from, to })
     }
 }
 
-pub fn part_one(input: &str) -> Option<String> {
+/// Split the input into the starting yard and the instructions that follow
+fn parse_input(input: &str) -> Result<(CrateYard, Vec<CrateInstruction>), YardError> {
     let lines_vec = input.lines().collect::<Vec<&str>>();
-    let mut parts = lines_vec.split(|element| element.is_empty());
+    let mut parts = lines_vec.splitn(2, |element| element.is_empty());
+
+    let yard_def = parts.next().unwrap_or_default();
+    let move_defs = parts
+        .next()
+        .ok_or_else(|| YardError::new(YardErrorKind::MissingInstructions))?;
+    // blank lines after the last move aren't instructions
+    let end = move_defs
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(0, |last| last + 1);
+    let move_defs = &move_defs[..end];
+
+    let yard = CrateYard::try_from(yard_def.to_vec())?;
+    let instructions = move_defs
+        .iter()
+        .enumerate()
+        .map(|(index, move_def)| {
+            // moves start after the drawing and the blank line below it
+            let line = yard_def.len() + index + 2;
+            CrateInstruction::try_from(*move_def)
+                .map_err(|e| e.on_line(line).at_instruction(index + 1))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let yard_def = parts.next().expect("No yard definition");
-    let move_defs = parts.next().expect("No move definitions");
+    Ok((yard, instructions))
+}
 
-    let mut yard = CrateYard::from(Into::<Vec<&str>>::into(yard_def));
+/// Run every instruction with the given crane
+fn simulate(input: &str, crane: &impl Crane) -> Result<CrateYard, YardError> {
+    let (mut yard, instructions) = parse_input(input)?;
+    for (index, instruction) in instructions.iter().enumerate() {
+        yard.apply(crane, instruction)
+            .map_err(|e| e.at_instruction(index + 1))?;
+    }
+
+    Ok(yard)
+}
+
+/// Run every instruction with the given crane and read the tops of the stacks
+fn solve(input: &str, crane: &impl Crane) -> Result<String, YardError> {
+    Ok(simulate(input, crane)?.top_readout())
+}
+
+/// Stacks in label order, as rearranged by the move planner
+type Stacks = Vec<Vec<Crate>>;
+
+/// Find moves that turn `start` into `target` with the given crane. Returns `None` when the
+/// yards don't hold the same stacks and crates, or no sequence of moves gets there.
+///
+/// With `minimal` set, or fewer than three stacks, this searches every possible move for the
+/// shortest plan, which is only practical for small yards. Otherwise it builds a plan of
+/// single-crate moves quickly, but without trying to keep it short.
+fn plan_moves(
+    start: &CrateYard,
+    target: &CrateYard,
+    crane: &impl Crane,
+    minimal: bool,
+) -> Option<Vec<CrateInstruction>> {
+    let crates = |yard: &CrateYard| {
+        let mut crates = yard.stacks.iter().flatten().cloned().collect::<Vec<_>>();
+        crates.sort_unstable();
+        crates
+    };
+    if start.labels != target.labels || crates(start) != crates(target) {
+        return None;
+    }
+
+    if minimal || start.stacks.len() < 3 {
+        search_moves(start, target, crane)
+    } else {
+        Some(single_moves(start, target))
+    }
+}
+
+/// The shortest plan, by breadth-first search over every move from every state
+fn search_moves(
+    start: &CrateYard,
+    target: &CrateYard,
+    crane: &impl Crane,
+) -> Option<Vec<CrateInstruction>> {
+    let labels = &start.labels;
+    let goal = &target.stacks;
+
+    let moves = |stacks: &Stacks| {
+        let mut next = vec![];
+        for from in 0..stacks.len() {
+            for to in (0..stacks.len()).filter(|&to| to != from) {
+                for quantity in 1..=stacks[from].len() {
+                    let mut moved = stacks.clone();
+                    let lifted = moved[from].split_off(stacks[from].len() - quantity);
+                    moved[to].extend(crane.land(lifted));
+                    next.push((moved, 1));
+                }
+            }
+        }
+        next
+    };
+    let path = bfs(&moves, start.stacks.clone(), |stacks| stacks == goal)?;
+
+    // Each move shrinks one stack and grows another by the same amount
+    let instructions = path
+        .nodes
+        .windows(2)
+        .map(|pair| {
+            let (before, after) = (&pair[0], &pair[1]);
+            let from = (0..before.len())
+                .find(|&i| after[i].len() < before[i].len())
+                .unwrap();
+            let to = (0..before.len())
+                .find(|&i| after[i].len() > before[i].len())
+                .unwrap();
+            CrateInstruction {
+                quantity: (before[from].len() - after[from].len()) as u32,
+                from: labels[from],
+                to: labels[to],
+            }
+        })
+        .collect();
+
+    Some(instructions)
+}
+
+/// Build the target one stack at a time, bottom up, moving one crate at a time. A single
+/// crate lands the same way whatever the crane, so the plan works for all of them.
+/// Needs at least three stacks, so there's always somewhere to put crates that are in the way.
+fn single_moves(start: &CrateYard, target: &CrateYard) -> Vec<CrateInstruction> {
+    let labels = &start.labels;
+    let goal = &target.stacks;
+    let mut stacks = start.stacks.clone();
+    let mut moves = vec![];
+
+    let mut shift = |stacks: &mut Stacks, from: usize, to: usize| {
+        let c = stacks[from].pop().unwrap();
+        stacks[to].push(c);
+        moves.push(CrateInstruction {
+            quantity: 1,
+            from: labels[from],
+            to: labels[to],
+        });
+    };
 
-    move_defs
+    // Crates at the bottom of each stack that are already where they end up
+    let mut locked = vec![0; stacks.len()];
+
+    for built in 0..stacks.len() {
+        // Somewhere to put crates that are in the way, preferring stacks still to be built
+        let spare = |avoid: usize| {
+            (built + 1..labels.len())
+                .chain(0..built)
+                .find(|&i| i != avoid)
+                .unwrap()
+        };
+
+        locked[built] = stacks[built]
+            .iter()
+            .zip(&goal[built])
+            .take_while(|(c, wanted)| c == wanted)
+            .count();
+        while stacks[built].len() > locked[built] {
+            shift(&mut stacks, built, spare(built));
+        }
+
+        for wanted in &goal[built][locked[built]..] {
+            // The copy of the crate with the fewest crates on top of it
+            let (from, depth) = (0..stacks.len())
+                .filter(|&s| s != built)
+                .filter_map(|s| {
+                    let depth = stacks[s][locked[s]..]
+                        .iter()
+                        .rev()
+                        .position(|c| c == wanted)?;
+                    Some((s, depth))
+                })
+                .min_by_key(|&(_, depth)| depth)
+                .unwrap();
+
+            for _ in 0..depth {
+                shift(&mut stacks, from, spare(from));
+            }
+            shift(&mut stacks, from, built);
+            locked[built] += 1;
+        }
+    }
+
+    moves
+}
+
+/// The yard before any move and after each one, captioned with the move just made
+fn frames(input: &str, crane: &impl Crane) -> Result<Vec<String>, YardError> {
+    let (mut yard, instructions) = parse_input(input)?;
+    let mut frames = vec![format!("start\n\n{}", yard)];
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        yard.apply(crane, instruction)
+            .map_err(|e| e.at_instruction(index + 1))?;
+        frames.push(format!(
+            "{}/{}: {}\n\n{}",
+            index + 1,
+            instructions.len(),
+            instruction,
+            yard
+        ));
+    }
+
+    Ok(frames)
+}
+
+/// The yard after the first `step` moves, captioned like an animation frame and followed by
+/// how the stacks changed with the last of those moves
+fn step_report(yard: &CrateYard, step: usize) -> Option<String> {
+    let after = yard.at_step(step)?;
+    let Some(before) = step.checked_sub(1).and_then(|step| yard.at_step(step)) else {
+        return Some(format!("start\n\n{}", after));
+    };
+
+    let changes = before
+        .diff(&after)
         .iter()
-        .for_each(|move_def| yard.apply_instruction_crane9000(CrateInstruction::from(*move_def)));
+        .map(|change| change.to_string())
+        .collect::<Vec<_>>();
+    Some(format!(
+        "{}/{}: {}\n\n{}\n\n{}",
+        step,
+        yard.history_len(),
+        after.log[step - 1].instruction,
+        after,
+        changes.join("\n")
+    ))
+}
+
+/// A puzzle input made of a starting yard and the moves to make
+fn puzzle_input(start: &CrateYard, moves: &[CrateInstruction]) -> String {
+    let moves = moves.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+    format!("{}\n\n{}", start, moves.join("\n"))
+}
 
-    Some(yard.top_readout())
+pub fn part_one(input: &str) -> Option<String> {
+    solve(input, &CrateMover9000)
+        .map_err(|e| eprintln!("Invalid crate yard: {}", e))
+        .ok()
 }
 
 pub fn part_two(input: &str) -> Option<String> {
-    let lines_vec = input.lines().collect::<Vec<&str>>();
-    let mut parts = lines_vec.split(|element| element.is_empty());
+    solve(input, &CrateMover9001)
+        .map_err(|e| eprintln!("Invalid crate yard: {}", e))
+        .ok()
+}
 
-    let yard_def = parts.next().expect("No yard definition");
-    let move_defs = parts.next().expect("No move definitions");
+struct Args {
+    crane: CraneModel,
+    /// Redraw the yard after every move, pausing this long between frames
+    animate: Option<Duration>,
+    /// Directory to write every state of the yard to, one file per frame
+    frames: Option<String>,
+    /// File with a yard drawing to plan moves towards, from the input's starting yard
+    plan: Option<String>,
+    minimal: bool,
+    /// Show the yard after this many moves, and what the last of them changed
+    step: Option<usize>,
+    /// Show the yard with this many of the final moves taken back
+    undo: Option<usize>,
+    /// After undoing, make this many of the undone moves again
+    redo: Option<usize>,
+}
+
+/// Parse how many crates a batch crane carries, which can't be zero
+fn parse_capacity(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(capacity) => Ok(capacity),
+        Err(e) => Err(e.to_string()),
+    }
+}
 
-    let mut yard = CrateYard::from(Into::<Vec<&str>>::into(yard_def));
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    let batch = args.opt_value_from_fn("--batch", parse_capacity)?;
+    let model: Option<String> = args.opt_value_from_str("--crane")?;
 
-    move_defs
-        .iter()
-        .for_each(|move_def| yard.apply_instruction_crane9001(CrateInstruction::from(*move_def)));
+    let crane = match (batch, model.as_deref()) {
+        (Some(capacity), _) => CraneModel::Batch(capacity),
+        (None, None | Some("9000")) => CraneModel::Mover9000,
+        (None, Some("9001")) => CraneModel::Mover9001,
+        (None, Some(other)) => {
+            return Err(pico_args::Error::Utf8ArgumentParsingFailed {
+                value: other.to_string(),
+                cause: "expected 9000 or 9001".to_string(),
+            })
+        }
+    };
+    let animate = args.contains("--animate");
+    let delay = args.opt_value_from_str("--delay")?.unwrap_or(500);
+
+    Ok(Args {
+        crane,
+        animate: animate.then(|| Duration::from_millis(delay)),
+        frames: args.opt_value_from_str("--frames")?,
+        plan: args.opt_value_from_str("--plan")?,
+        minimal: args.contains("--minimal"),
+        step: args.opt_value_from_str("--step")?,
+        undo: args.opt_value_from_str("--undo")?,
+        redo: args.opt_value_from_str("--redo")?,
+    })
+}
 
-    Some(yard.top_readout())
+/// Write each frame to its own numbered file in `dir`
+fn write_frames(dir: &Path, frames: &[String]) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let digits = frames.len().to_string().len();
+    for (index, frame) in frames.iter().enumerate() {
+        let path = dir.join(format!("{:0width$}.txt", index, width = digits));
+        fs::write(path, format!("{}\n", frame))?;
+    }
+    Ok(())
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 5);
+
+    if let Some(path) = args.plan {
+        let (start, _) = parse_input(input).unwrap_or_else(|e| {
+            eprintln!("Invalid crate yard: {}", e);
+            std::process::exit(1);
+        });
+        let drawing = fs::read_to_string(&path).unwrap_or_else(|e| {
+            eprintln!("Failed to read {}: {}", path, e);
+            std::process::exit(1);
+        });
+        let target = CrateYard::try_from(drawing.lines().collect::<Vec<_>>()).unwrap_or_else(|e| {
+            eprintln!("Invalid target yard: {}", e);
+            std::process::exit(1);
+        });
+
+        match plan_moves(&start, &target, &args.crane, args.minimal) {
+            Some(moves) => println!("{}", puzzle_input(&start, &moves)),
+            None => {
+                eprintln!("No moves turn the starting yard into the one in {}", path);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if args.step.is_some() || args.undo.is_some() {
+        let yard = simulate(input, &args.crane).unwrap_or_else(|e| {
+            eprintln!("Invalid crate yard: {}", e);
+            std::process::exit(1);
+        });
+
+        if let Some(step) = args.step {
+            match step_report(&yard, step) {
+                Some(report) => println!("{}", report),
+                None => {
+                    eprintln!("There are only {} moves", yard.history_len());
+                    std::process::exit(1);
+                }
+            }
+        } else if let Some(moves) = args.undo {
+            let mut rewound = yard.clone();
+            for instruction in rewound.undo(moves) {
+                println!("undo {}", instruction);
+            }
+            for instruction in rewound.redo(args.redo.unwrap_or(0)) {
+                println!("redo {}", instruction);
+            }
+            println!("\n{}", rewound);
+        }
+        return;
+    }
+
+    if args.animate.is_some() || args.frames.is_some() {
+        let frames = frames(input, &args.crane).unwrap_or_else(|e| {
+            eprintln!("Invalid crate yard: {}", e);
+            std::process::exit(1);
+        });
+
+        if let Some(dir) = args.frames {
+            if let Err(e) = write_frames(Path::new(&dir), &frames) {
+                eprintln!("Failed to write frames to {}: {}", dir, e);
+                std::process::exit(1);
+            }
+            println!("Wrote {} frames to {}", frames.len(), dir);
+        } else if let Some(delay) = args.animate {
+            for frame in frames {
+                // Clear the terminal and draw from the top left
+                println!("\x1b[2J\x1b[H{}", frame);
+                thread::sleep(delay);
+            }
+        }
+        return;
+    }
+
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    if let CraneModel::Batch(capacity) = args.crane {
+        match solve(input, &args.crane) {
+            Ok(readout) => println!("Moving {} at a time: {}", capacity, readout),
+            Err(e) => eprintln!("Invalid crate yard: {}", e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -162,32 +914,44 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
+    /// A yard of single-letter crates with stacks labelled from 1
+    fn yard(stacks: &[&str]) -> CrateYard {
+        CrateYard::new(
+            (1..=stacks.len() as u32).collect(),
+            stacks
+                .iter()
+                .map(|stack| stack.chars().map(String::from).collect())
+                .collect(),
+        )
+    }
+
     #[rstest]
-    #[case(CrateYard{ crates: HashMap::from([(1, vec!['Z', 'N']), (2, vec!['M', 'C', 'D']), (3, vec!['P'])]) }, CrateInstruction {quantity: 1, from: 2, to: 1},CrateYard{ crates: HashMap::from([(1, vec!['Z', 'N', 'D']), (2, vec!['M', 'C']), (3, vec!['P'])]) } )]
-    #[case(CrateYard{ crates: HashMap::from([(1, vec!['Z', 'N', 'D']), (2, vec!['M', 'C']), (3, vec!['P'])]) }, CrateInstruction {quantity: 3, from: 1, to: 3},CrateYard{ crates: HashMap::from([(1, vec![]), (2, vec!['M', 'C']), (3, vec!['P', 'D', 'N', 'Z'])]) } )]
+    #[case(yard(&["ZN", "MCD", "P"]), CrateInstruction {quantity: 1, from: 2, to: 1}, yard(&["ZND", "MC", "P"]))]
+    #[case(yard(&["ZND", "MC", "P"]), CrateInstruction {quantity: 3, from: 1, to: 3}, yard(&["", "MC", "PDNZ"]))]
     fn test_apply_crate_instruction_9000(
         #[case] mut state: CrateYard,
         #[case] instruction: CrateInstruction,
         #[case] expected_state: CrateYard,
     ) {
-        state.apply_instruction_crane9000(instruction);
+        state.apply(&CrateMover9000, &instruction).unwrap();
         assert_eq!(state, expected_state);
     }
 
     #[rstest]
-    #[case(CrateYard{ crates: HashMap::from([(1, vec!['Z', 'N']), (2, vec!['M', 'C', 'D']), (3, vec!['P'])]) }, CrateInstruction {quantity: 1, from: 2, to: 1},CrateYard{ crates: HashMap::from([(1, vec!['Z', 'N', 'D']), (2, vec!['M', 'C']), (3, vec!['P'])]) } )]
-    #[case(CrateYard{ crates: HashMap::from([(1, vec!['Z', 'N', 'D']), (2, vec!['M', 'C']), (3, vec!['P'])]) }, CrateInstruction {quantity: 3, from: 1, to: 3},CrateYard{ crates: HashMap::from([(1, vec![]), (2, vec!['M', 'C']), (3, vec!['P', 'Z', 'N', 'D'])]) } )]
+    #[case(yard(&["ZN", "MCD", "P"]), CrateInstruction {quantity: 1, from: 2, to: 1}, yard(&["ZND", "MC", "P"]))]
+    #[case(yard(&["ZND", "MC", "P"]), CrateInstruction {quantity: 3, from: 1, to: 3}, yard(&["", "MC", "PZND"]))]
     fn test_apply_crate_instruction_9001(
         #[case] mut state: CrateYard,
         #[case] instruction: CrateInstruction,
         #[case] expected_state: CrateYard,
     ) {
-        state.apply_instruction_crane9001(instruction);
+        state.apply(&CrateMover9001, &instruction).unwrap();
         assert_eq!(state, expected_state);
     }
 
     #[rstest]
-    #[case(CrateYard{ crates: HashMap::from([(1, vec!['C']), (2, vec!['M']), (3, vec!['P', 'D', 'N', 'Z'])]) }, "CMZ")]
+    #[case(yard(&["C", "M", "PDNZ"]), "CMZ")]
+    #[case(yard(&["C", "", "PDNZ"]), "CZ")]
     fn test_top_readout(#[case] state: CrateYard, #[case] expected_readout: &str) {
         assert_eq!(state.top_readout(), expected_readout);
     }
@@ -195,34 +959,488 @@ mod tests {
     #[test]
     fn test_parse_crate_yard() {
         assert_eq!(
-            CrateYard::from(vec![
+            CrateYard::try_from(vec![
                 "    [D]    ",
                 "[N] [C]    ",
                 "[Z] [M] [P]",
                 " 1   2   3 "
             ]),
-            CrateYard {
-                crates: HashMap::from([
-                    (1, vec!['Z', 'N']),
-                    (2, vec!['M', 'C', 'D']),
-                    (3, vec!['P'])
-                ])
-            }
+            Ok(yard(&["ZN", "MCD", "P"]))
         );
     }
 
     #[test]
     fn test_parse_crate_instruction() {
         assert_eq!(
-            CrateInstruction::from("move 3 from 1 to 2"),
-            CrateInstruction {
+            CrateInstruction::try_from("move 3 from 1 to 2"),
+            Ok(CrateInstruction {
                 quantity: 3,
                 from: 1,
                 to: 2
-            }
+            })
+        );
+    }
+
+    fn example_yard() -> CrateYard {
+        yard(&["ZN", "MCD", "P"])
+    }
+
+    #[rstest]
+    #[case(vec!["[A]", " 1 x "], YardErrorKind::MalformedLabel("x".to_string()))]
+    #[case(vec!["[A]", " 1   1 "], YardErrorKind::MalformedLabel("1".to_string()))]
+    #[case(vec!["[A]", "   "], YardErrorKind::MalformedLabel("   ".to_string()))]
+    #[case(vec!["[A] [B] [C]", " 1   2 "], YardErrorKind::RaggedRow(1))]
+    #[case(vec!["    [D]", "[N][C] ", " 1   2 "], YardErrorKind::RaggedRow(2))]
+    #[case(vec!["[N] [ ]", " 1   2 "], YardErrorKind::RaggedRow(1))]
+    #[case(vec![" N   C ", " 1   2 "], YardErrorKind::RaggedRow(1))]
+    #[case(vec!["  [A]", " 1   2 "], YardErrorKind::RaggedRow(1))]
+    #[case(vec!["[ABCDE]", " 1   2 "], YardErrorKind::RaggedRow(1))]
+    fn test_parse_crate_yard_errors(#[case] lines: Vec<&str>, #[case] expected: YardErrorKind) {
+        assert_eq!(CrateYard::try_from(lines), Err(YardError::new(expected)));
+    }
+
+    #[rstest]
+    #[case(CrateInstruction { quantity: 1, from: 4, to: 1 }, YardErrorKind::UnknownStack(4))]
+    #[case(CrateInstruction { quantity: 1, from: 1, to: 0 }, YardErrorKind::UnknownStack(0))]
+    #[case(CrateInstruction { quantity: 2, from: 3, to: 1 }, YardErrorKind::TooManyCrates { stack: 3, requested: 2, available: 1 })]
+    fn test_invalid_moves(#[case] instruction: CrateInstruction, #[case] expected: YardErrorKind) {
+        let mut yard = example_yard();
+        assert_eq!(
+            yard.apply(&CrateMover9000, &instruction),
+            Err(YardError::new(expected.clone()))
+        );
+        assert_eq!(
+            yard.apply(&CrateMover9001, &instruction),
+            Err(YardError::new(expected))
+        );
+        assert_eq!(yard, example_yard());
+    }
+
+    #[test]
+    fn test_errors_carry_instruction_index() {
+        let error = parse_input("[A]\n 1 \n\nmove 1 from 1 to 1\nmove one from 1 to 1")
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(error.instruction, Some(2));
+        assert!(matches!(error.kind, YardErrorKind::MalformedInstruction(_)));
+        assert_eq!(
+            error.to_string(),
+            "instruction 2: line 5, column 6: invalid value \"one\""
+        );
+
+        let input = advent_of_code::read_file("examples", 5).replace("move 2 from", "move 2 frm");
+        assert_eq!(
+            parse_input(&input).map(|_| ()).unwrap_err().to_string(),
+            "instruction 3: line 8, column 6: expected \" from \""
+        );
+
+        assert_eq!(
+            parse_input("[A]\n 1 \nmove 1 from 1 to 1").map(|_| ()),
+            Err(YardError::new(YardErrorKind::MissingInstructions))
+        );
+    }
+
+    #[rstest]
+    #[case("\n\n", "CMZ", "MCD")]
+    #[case("\n \n\n", "CMZ", "MCD")]
+    fn test_trailing_blank_lines(
+        #[case] trailing: &str,
+        #[case] expected_one: &str,
+        #[case] expected_two: &str,
+    ) {
+        let input = advent_of_code::read_file("examples", 5) + trailing;
+        assert_eq!(part_one(&input), Some(expected_one.to_string()));
+        assert_eq!(part_two(&input), Some(expected_two.to_string()));
+    }
+
+    #[rstest]
+    #[case(part_one)]
+    #[case(part_two)]
+    fn test_parts_reject_invalid_moves(#[case] part: fn(&str) -> Option<String>) {
+        assert_eq!(
+            part("[A]\n 1 \n\nmove 1 from 1 to 1\nmove 2 from 1 to 1"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_error_display() {
+        let error = YardError::new(YardErrorKind::TooManyCrates {
+            stack: 2,
+            requested: 5,
+            available: 3,
+        })
+        .at_instruction(7);
+        assert_eq!(
+            error.to_string(),
+            "instruction 7: cannot take 5 crates from stack 2, which holds 3"
+        );
+    }
+
+    #[test]
+    fn test_display_yard() {
+        let input = advent_of_code::read_file("examples", 5);
+        let drawing = input.split("\n\n").next().unwrap();
+        let yard = CrateYard::try_from(drawing.lines().collect::<Vec<_>>()).unwrap();
+
+        assert_eq!(yard.to_string(), drawing);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mut yard = example_yard();
+        yard.apply(
+            &CrateMover9000,
+            &CrateInstruction {
+                quantity: 3,
+                from: 2,
+                to: 3,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            yard.to_string(),
+            "        [M]\n        [C]\n[N]     [D]\n[Z]     [P]\n 1   2   3 "
+        );
+
+        let redrawn = yard.to_string();
+        assert_eq!(
+            CrateYard::try_from(redrawn.lines().collect::<Vec<_>>()),
+            Ok(yard)
+        );
+    }
+
+    #[test]
+    fn test_multi_character_crates() {
+        let yard = CrateYard::try_from(vec!["[Cu]", "[Fe] [Zn]  [O]", "  1    2    3"]).unwrap();
+        assert_eq!(
+            yard,
+            CrateYard::new(
+                vec![1, 2, 3],
+                vec![
+                    vec!["Fe".to_string(), "Cu".to_string()],
+                    vec!["Zn".to_string()],
+                    vec!["O".to_string()],
+                ],
+            )
+        );
+        assert_eq!(yard.top_readout(), "CuZnO");
+
+        let redrawn = yard.to_string();
+        assert_eq!(redrawn, "[Cu]         \n[Fe] [Zn] [O]\n 1    2    3 ");
+        assert_eq!(
+            CrateYard::try_from(redrawn.lines().collect::<Vec<_>>()),
+            Ok(yard)
+        );
+    }
+
+    const TWELVE_STACKS: &str = "\
+[L]
+[A] [B] [C] [D] [E] [F] [G] [H] [I] [J] [K] [M]
+ 1   2   3   4   5   6   7   8   9  10  11  12 
+
+move 2 from 1 to 10
+move 1 from 12 to 11
+move 3 from 10 to 2
+";
+
+    #[test]
+    fn test_twelve_stacks() {
+        let drawing = TWELVE_STACKS.split("\n\n").next().unwrap();
+        let yard = CrateYard::try_from(drawing.lines().collect::<Vec<_>>()).unwrap();
+
+        assert_eq!(yard.labels, (1..=12).collect::<Vec<_>>());
+        assert_eq!(yard.stacks[0], vec!["A".to_string(), "L".to_string()]);
+        assert_eq!(yard.top_readout(), "LBCDEFGHIJKM");
+
+        let redrawn = yard.to_string();
+        assert_eq!(
+            CrateYard::try_from(redrawn.lines().collect::<Vec<_>>()),
+            Ok(yard)
+        );
+    }
+
+    #[rstest]
+    #[case(CraneModel::Mover9000, "JCDEFGHIM")]
+    #[case(CraneModel::Mover9001, "LCDEFGHIM")]
+    fn test_twelve_stack_moves(#[case] crane: CraneModel, #[case] expected: &str) {
+        assert_eq!(solve(TWELVE_STACKS, &crane), Ok(expected.to_string()));
+    }
+
+    #[test]
+    fn test_frames() {
+        let input = advent_of_code::read_file("examples", 5);
+        let frames = frames(&input, &CrateMover9001).unwrap();
+
+        assert_eq!(frames.len(), 5);
+        assert!(frames[0].starts_with("start\n\n    [D]    \n"));
+        assert_eq!(
+            frames[4],
+            "4/4: move 1 from 1 to 2\n\n        [D]\n        [N]\n        [Z]\n[M] [C] [P]\n 1   2   3 "
         );
     }
 
+    #[rstest]
+    #[case(CraneModel::Mover9000)]
+    #[case(CraneModel::Mover9001)]
+    #[case(CraneModel::Batch(2))]
+    fn test_at_step_matches_replay(#[case] crane: CraneModel) {
+        let input = advent_of_code::read_file("examples", 5);
+        let (start, instructions) = parse_input(&input).unwrap();
+        let yard = simulate(&input, &crane).unwrap();
+
+        for step in 0..=instructions.len() {
+            let expected = replay(&start, &instructions[..step], &crane);
+            let at_step = yard.at_step(step).unwrap();
+            assert_eq!(at_step, expected);
+            assert_eq!(at_step.log.len(), step);
+        }
+        assert_eq!(yard.at_step(instructions.len() + 1), None);
+    }
+
+    #[test]
+    fn test_undo() {
+        let input = advent_of_code::read_file("examples", 5);
+        let mut rewound = simulate(&input, &CrateMover9000).unwrap();
+
+        let undone = rewound.undo(2);
+        assert_eq!(
+            undone.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+            ["move 1 from 1 to 2", "move 2 from 2 to 1"]
+        );
+        assert_eq!(rewound, yard(&["", "MC", "PDNZ"]));
+        assert_eq!(rewound.log.len(), 2);
+
+        assert_eq!(rewound.undo(10).len(), 2);
+        assert_eq!(rewound, example_yard());
+        assert!(rewound.undo(1).is_empty());
+    }
+
+    #[rstest]
+    #[case(CraneModel::Mover9000)]
+    #[case(CraneModel::Mover9001)]
+    #[case(CraneModel::Batch(2))]
+    fn test_undo_redo_round_trip(#[case] crane: CraneModel) {
+        let input = advent_of_code::read_file("examples", 5);
+        let (start, instructions) = parse_input(&input).unwrap();
+        let finished = simulate(&input, &crane).unwrap();
+
+        let mut yard = finished.clone();
+        yard.undo(3);
+        assert_eq!(yard, replay(&start, &instructions[..1], &crane));
+
+        assert_eq!(yard.redo(2), &instructions[1..3]);
+        assert_eq!(yard, replay(&start, &instructions[..3], &crane));
+        assert_eq!(yard.at_step(4), Some(finished.clone()));
+
+        assert_eq!(yard.redo(10), &instructions[3..]);
+        assert_eq!(yard, finished);
+        assert_eq!(yard.log, finished.log);
+        assert!(yard.redo(1).is_empty());
+    }
+
+    #[test]
+    fn test_apply_clears_redo() {
+        let input = advent_of_code::read_file("examples", 5);
+        let mut rewound = simulate(&input, &CrateMover9000).unwrap();
+        rewound.undo(2);
+
+        let instruction = CrateInstruction {
+            quantity: 1,
+            from: 3,
+            to: 2,
+        };
+        rewound.apply(&CrateMover9000, &instruction).unwrap();
+        assert!(rewound.redo(1).is_empty());
+        assert_eq!(rewound.history_len(), 3);
+        assert_eq!(rewound.at_step(4), None);
+        assert_eq!(rewound, yard(&["", "MCZ", "PDN"]));
+    }
+
+    #[rstest]
+    #[case(yard(&["ZN", "MCD", "P"]), yard(&["ZND", "MC", "P"]), &["stack 1: +[D]", "stack 2: -[D]"])]
+    #[case(yard(&["ZN", "MCD", "P"]), yard(&["ZN", "MCD", "P"]), &[])]
+    #[case(yard(&["AB", "C"]), yard(&["AC", "C", "D"]), &["stack 1: -[B] +[C]", "stack 3: +[D]"])]
+    #[case(yard(&["AB", "C"]), yard(&["AB"]), &["stack 2: -[C]"])]
+    fn test_diff(#[case] before: CrateYard, #[case] after: CrateYard, #[case] expected: &[&str]) {
+        let changes = before.diff(&after);
+        assert_eq!(
+            changes.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_step_report() {
+        let input = advent_of_code::read_file("examples", 5);
+        let yard = simulate(&input, &CrateMover9000).unwrap();
+
+        assert_eq!(
+            step_report(&yard, 2).unwrap(),
+            "2/4: move 3 from 1 to 3\n\n        [Z]\n        [N]\n    [C] [D]\n    [M] [P]\n 1   2   3 \n\nstack 1: -[Z][N][D]\nstack 3: +[D][N][Z]"
+        );
+        assert_eq!(
+            step_report(&yard, 0).unwrap(),
+            format!("start\n\n{}", example_yard())
+        );
+        assert_eq!(step_report(&yard, 5), None);
+    }
+
+    fn replay(start: &CrateYard, moves: &[CrateInstruction], crane: &impl Crane) -> CrateYard {
+        let mut yard = start.clone();
+        for instruction in moves {
+            yard.apply(crane, instruction).unwrap();
+        }
+        yard
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn test_plan_round_trips_example(#[case] minimal: bool) {
+        let input = advent_of_code::read_file("examples", 5);
+        let (start, _) = parse_input(&input).unwrap();
+
+        let target = simulate(&input, &CrateMover9000).unwrap();
+        let moves = plan_moves(&start, &target, &CrateMover9000, minimal).unwrap();
+        assert_eq!(replay(&start, &moves, &CrateMover9000), target);
+
+        let target = simulate(&input, &CrateMover9001).unwrap();
+        let moves = plan_moves(&start, &target, &CrateMover9001, minimal).unwrap();
+        assert_eq!(replay(&start, &moves, &CrateMover9001), target);
+        if minimal {
+            assert!(moves.len() <= 4);
+        }
+    }
+
+    #[test]
+    fn test_single_moves_work_for_any_crane() {
+        let start = yard(&["ABCDE", "FG", "", "HIJ"]);
+        let target = yard(&["JIH", "", "GFEDCBA", ""]);
+        let moves = plan_moves(&start, &target, &CrateMover9001, false).unwrap();
+
+        assert!(moves.iter().all(|m| m.quantity == 1));
+        assert_eq!(replay(&start, &moves, &CrateMover9000), target);
+        assert_eq!(replay(&start, &moves, &CrateMover9001), target);
+        assert_eq!(replay(&start, &moves, &BatchCrane { capacity: 3 }), target);
+    }
+
+    #[test]
+    fn test_plan_with_duplicate_crates() {
+        let start = yard(&["AAB", "BA", "A"]);
+        let target = yard(&["BBA", "AA", "A"]);
+        let moves = plan_moves(&start, &target, &CrateMover9000, false).unwrap();
+        assert_eq!(replay(&start, &moves, &CrateMover9000), target);
+    }
+
+    #[test]
+    fn test_minimal_plan_depends_on_crane() {
+        let start = example_yard();
+        let target = yard(&["ZN", "M", "PCD"]);
+
+        let moves = plan_moves(&start, &target, &CrateMover9001, true).unwrap();
+        assert_eq!(
+            moves,
+            vec![CrateInstruction {
+                quantity: 2,
+                from: 2,
+                to: 3
+            }]
+        );
+        assert_eq!(
+            plan_moves(&start, &target, &CrateMover9000, true).map(|m| m.len()),
+            Some(2)
+        );
+    }
+
+    #[rstest]
+    #[case(yard(&["AB", ""]), yard(&["A", ""]))]
+    #[case(yard(&["AB", ""]), yard(&["AC", ""]))]
+    #[case(yard(&["AB", ""]), yard(&["AB", "", ""]))]
+    fn test_plan_mismatched_yards(#[case] start: CrateYard, #[case] target: CrateYard) {
+        assert_eq!(plan_moves(&start, &target, &CrateMover9001, false), None);
+    }
+
+    #[test]
+    fn test_plan_with_two_stacks() {
+        // Moving crates one at a time between two stacks can never swap them
+        let start = yard(&["AB", ""]);
+        let target = yard(&["BA", ""]);
+        assert_eq!(plan_moves(&start, &target, &CrateMover9000, false), None);
+
+        let moves = plan_moves(&start, &target, &CrateMover9001, false).unwrap();
+        assert_eq!(moves.len(), 3);
+        assert_eq!(replay(&start, &moves, &CrateMover9001), target);
+    }
+
+    #[test]
+    fn test_generated_puzzle_input() {
+        let start = yard(&["ZN", "MCD", "P"]);
+        let target = yard(&["D", "", "PNZCM"]);
+        let moves = plan_moves(&start, &target, &CrateMover9000, false).unwrap();
+        let input = puzzle_input(&start, &moves);
+
+        assert_eq!(simulate(&input, &CrateMover9000), Ok(target));
+    }
+
+    #[rstest]
+    #[case(1, vec![1, 2, 3, 4, 5], vec![5, 4, 3, 2, 1])]
+    #[case(2, vec![1, 2, 3, 4, 5], vec![4, 5, 2, 3, 1])]
+    #[case(3, vec![1, 2, 3, 4, 5], vec![3, 4, 5, 1, 2])]
+    #[case(5, vec![1, 2, 3, 4, 5], vec![1, 2, 3, 4, 5])]
+    fn test_batch_crane(
+        #[case] capacity: usize,
+        #[case] lifted: Vec<u32>,
+        #[case] expected: Vec<u32>,
+    ) {
+        assert_eq!(BatchCrane { capacity }.land(lifted), expected);
+    }
+
+    #[rstest]
+    #[case("3", Ok(3))]
+    #[case("0", Err("must be at least 1".to_string()))]
+    #[case("x", Err("invalid digit found in string".to_string()))]
+    fn test_parse_capacity(#[case] value: &str, #[case] expected: Result<usize, String>) {
+        assert_eq!(parse_capacity(value), expected);
+    }
+
+    /// A bigger yard than the example, with moves of every size
+    const BIG_YARD: &str = "\
+[A]     [K]
+[B] [G] [L]
+[C] [H] [M]
+[D] [I] [N]
+[E] [J] [O] [Q]
+[F] [P] [R] [S]
+ 1   2   3   4
+
+move 4 from 1 to 2
+move 6 from 2 to 4
+move 2 from 3 to 1
+move 3 from 4 to 3
+move 5 from 4 to 2
+move 5 from 3 to 1";
+
+    #[rstest]
+    #[case(advent_of_code::read_file("examples", 5))]
+    #[case(BIG_YARD.to_string())]
+    fn test_single_crate_batches_match_9000(#[case] input: String) {
+        assert_eq!(
+            solve(&input, &BatchCrane { capacity: 1 }),
+            solve(&input, &CrateMover9000)
+        );
+    }
+
+    #[rstest]
+    #[case(advent_of_code::read_file("examples", 5))]
+    #[case(BIG_YARD.to_string())]
+    fn test_unlimited_batches_match_9001(#[case] input: String) {
+        let unlimited = BatchCrane {
+            capacity: usize::MAX,
+        };
+        assert_eq!(solve(&input, &unlimited), solve(&input, &CrateMover9001));
+    }
+
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 5);
@@ -9,7 +9,7 @@ fn find_marker(input: &str, message_size: usize) -> Option<usize> {
                 .len()
                 == message_size
         })
-        .map(|ind| (ind + message_size))
+        .map(|ind| ind + message_size)
 }
 
 pub fn part_one(input: &str) -> Option<usize> {
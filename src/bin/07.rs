@@ -1,175 +1,399 @@
-use std::collections::{HashMap, HashSet};
+use std::{collections::BTreeMap, fmt};
 
-// References used: https://aloso.github.io/2021/03/09/creating-an-iterator
+/// Index of a node in the filesystem arena
+type NodeId = usize;
 
-#[derive(PartialEq, Debug)]
-struct Filesystem {
-    /// Size of this element itself, not including any children
-    size: u32,
+/// The root directory is always the first node
+const ROOT: NodeId = 0;
+
+/// An absolute location in the filesystem, as the names leading down from the root
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct FsPath(Vec<String>);
 
-    /// Children of the element, i.e. contents of a directory
-    children: Option<HashMap<String, Filesystem>>,
+impl fmt::Display for FsPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "/");
+        }
+        self.0.iter().try_for_each(|name| write!(f, "/{}", name))
+    }
+}
+
+impl From<&str> for FsPath {
+    /// Parse an absolute path like "/a/e"
+    fn from(path: &str) -> Self {
+        FsPath(
+            path.split('/')
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug)]
+enum NodeKind {
+    File,
+    Dir(BTreeMap<String, NodeId>),
+}
+
+#[derive(Debug)]
+struct Node {
+    name: String,
+    parent: Option<NodeId>,
+    /// Size of a file, or the cached total size of everything inside a directory
+    size: u64,
+    kind: NodeKind,
+}
+
+/// A directory tree stored as an arena of nodes with parent links
+#[derive(Debug)]
+struct Filesystem {
+    nodes: Vec<Node>,
 }
 
 impl Filesystem {
-    /// A file
-    fn new_file(size: u32) -> Filesystem {
+    /// An empty filesystem with just the root directory
+    fn new() -> Filesystem {
         Filesystem {
-            size,
-            children: None,
+            nodes: vec![Node {
+                name: String::new(),
+                parent: None,
+                size: 0,
+                kind: NodeKind::Dir(BTreeMap::new()),
+            }],
         }
     }
 
-    /// A directory
-    fn new_dir(children: HashMap<String, Filesystem>) -> Filesystem {
-        Filesystem {
-            size: 0,
-            children: Some(children),
+    fn is_dir(&self, id: NodeId) -> bool {
+        matches!(self.nodes[id].kind, NodeKind::Dir(_))
+    }
+
+    fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id].parent
+    }
+
+    fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        match &self.nodes[id].kind {
+            NodeKind::Dir(children) => children.get(name).copied(),
+            NodeKind::File => None,
         }
     }
 
-    fn is_dir(&self) -> bool {
-        matches!(self.children, Some(_))
+    /// Children of a directory, ordered by name
+    fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let children = match &self.nodes[id].kind {
+            NodeKind::Dir(children) => Some(children.values().copied()),
+            NodeKind::File => None,
+        };
+        children.into_iter().flatten()
     }
 
-    /// Get a mutable reference to a nested element
-    fn _get_mut_at(&mut self, loc: &[String]) -> Option<&mut Filesystem> {
-        let mut current_location = self;
+    /// Find the node at a path
+    #[cfg(test)]
+    fn get_at(&self, path: &FsPath) -> Option<NodeId> {
+        path.0
+            .iter()
+            .try_fold(ROOT, |id, name| self.child(id, name))
+    }
 
-        let mut loc_internal = loc.to_owned();
-        loc_internal.reverse();
+    /// Absolute path of a node
+    fn path_of(&self, id: NodeId) -> FsPath {
+        let mut names = vec![];
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            names.push(self.nodes[current].name.clone());
+            current = parent;
+        }
+        names.reverse();
 
-        loop {
-            if let Some(item) = loc_internal.pop() {
-                if let Some(children) = &mut current_location.children {
-                    if let Some(child) = children.get_mut(&item.to_string()) {
-                        current_location = child;
-                    } else {
-                        return None;
-                    }
-                } else {
-                    return None;
-                }
-            } else {
-                return Some(current_location);
+        FsPath(names)
+    }
+
+    fn insert(
+        &mut self,
+        dir: NodeId,
+        name: &str,
+        size: u64,
+        kind: NodeKind,
+    ) -> Result<NodeId, ShellErrorKind> {
+        if matches!(name, "" | "." | "..") || name.contains('/') {
+            return Err(ShellErrorKind::InvalidName(name.to_string()));
+        }
+
+        // check every enclosing directory can hold the new size before changing anything.
+        let mut ancestor = Some(dir);
+        while let Some(current) = ancestor {
+            if self.nodes[current].size.checked_add(size).is_none() {
+                return Err(ShellErrorKind::SizeOverflow(self.path_of(current)));
             }
+            ancestor = self.parent(current);
         }
-    }
 
-    /// Insert a new entry in a directory
-    fn insert_at(&mut self, loc: &[String], key: String, value: Filesystem) {
-        self._get_mut_at(loc)
-            .expect("No entry at location")
-            .children
-            .as_mut()
-            .expect("Entry is not a directory")
-            .insert(key, value);
-    }
+        let id = self.nodes.len();
+        match &mut self.nodes[dir].kind {
+            NodeKind::Dir(children) => children.insert(name.to_string(), id),
+            NodeKind::File => return Err(ShellErrorKind::NotADirectory(self.path_of(dir))),
+        };
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(dir),
+            size,
+            kind,
+        });
+
+        // keep the cached size of every enclosing directory up to date.
+        let mut ancestor = Some(dir);
+        while let Some(current) = ancestor {
+            self.nodes[current].size += size;
+            ancestor = self.parent(current);
+        }
 
-    /// Get an immutable reference to a nested element
-    fn get_at(&self, loc: &[String]) -> Option<&Filesystem> {
-        let mut current_location = self;
+        Ok(id)
+    }
 
-        let mut loc_internal = loc.to_owned();
-        loc_internal.reverse();
+    /// Insert an empty directory, or return the existing directory with that name
+    fn insert_dir(&mut self, dir: NodeId, name: &str) -> Result<NodeId, ShellErrorKind> {
+        match self.child(dir, name) {
+            Some(existing) if self.is_dir(existing) => Ok(existing),
+            Some(existing) => Err(ShellErrorKind::ConflictingKind(self.path_of(existing))),
+            None => self.insert(dir, name, 0, NodeKind::Dir(BTreeMap::new())),
+        }
+    }
 
-        loop {
-            if let Some(item) = loc_internal.pop() {
-                if let Some(children) = &current_location.children {
-                    if let Some(child) = children.get(&item.to_string()) {
-                        current_location = child;
-                    } else {
-                        return None;
-                    }
-                } else {
-                    return None;
-                }
-            } else {
-                return Some(current_location);
+    /// Insert a file, or return the existing file if it was already seen with the same size
+    fn insert_file(
+        &mut self,
+        dir: NodeId,
+        name: &str,
+        size: u64,
+    ) -> Result<NodeId, ShellErrorKind> {
+        match self.child(dir, name) {
+            Some(existing) if self.is_dir(existing) => {
+                Err(ShellErrorKind::ConflictingKind(self.path_of(existing)))
             }
+            Some(existing) if self.nodes[existing].size != size => {
+                Err(ShellErrorKind::ConflictingSize {
+                    path: self.path_of(existing),
+                    previous: self.nodes[existing].size,
+                    listed: size,
+                })
+            }
+            Some(existing) => Ok(existing),
+            None => self.insert(dir, name, size, NodeKind::File),
         }
     }
 
-    // Get total size of an item, counting contents recursively
-    fn total_size(&self) -> u32 {
-        self.size
-            + self
-                .children
-                .as_ref()
-                .unwrap_or(&HashMap::new())
-                .iter()
-                .map(|(_, entry)| entry.total_size())
-                .sum::<u32>()
+    /// Insert a file at a path, creating any missing directories on the way
+    #[cfg(test)]
+    fn add_file(&mut self, path: &FsPath, size: u64) -> NodeId {
+        let (name, dirs) = path.0.split_last().expect("Path has no file name");
+        let dir = dirs.iter().fold(ROOT, |id, dir_name| {
+            self.insert_dir(id, dir_name).expect("Invalid path")
+        });
+
+        self.insert_file(dir, name, size).expect("Invalid path")
     }
 
-    fn _walk(&self, prefix_location: Vec<String>) -> HashSet<Vec<String>> {
-        let mut result: HashSet<Vec<String>> = HashSet::new();
+    /// Get total size of an item, counting contents recursively
+    fn total_size(&self, id: NodeId) -> u64 {
+        self.nodes[id].size
+    }
 
-        if let Some(children) = &self.children {
-            children.iter().for_each(|(name, fs)| {
-                let mut nested_loc = prefix_location.clone();
-                nested_loc.push(name.clone());
-                result.insert(nested_loc.clone());
+    /// Get all nodes, parents before their children
+    fn walk(&self) -> Vec<NodeId> {
+        let mut result = vec![];
+        let mut stack = vec![ROOT];
 
-                result.extend(fs._walk(nested_loc.clone()));
-            })
+        while let Some(id) = stack.pop() {
+            result.push(id);
+            let mut children = self.children(id).collect::<Vec<_>>();
+            children.reverse();
+            stack.extend(children);
         }
 
         result
     }
 
-    /// Get all recursive locations
-    fn walk(&self) -> HashSet<Vec<String>> {
-        let mut result: HashSet<Vec<String>> = HashSet::from([vec![]]);
-        result.extend(self._walk(vec![]));
+    /// Get all directories, parents before their children
+    fn directories(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.walk().into_iter().filter(|&id| self.is_dir(id))
+    }
 
-        result
+    /// Compare the subtrees under two nodes by name, kind and size
+    fn same_tree(&self, id: NodeId, other: &Filesystem, other_id: NodeId) -> bool {
+        let (node, other_node) = (&self.nodes[id], &other.nodes[other_id]);
+
+        match (&node.kind, &other_node.kind) {
+            (NodeKind::File, NodeKind::File) => node.size == other_node.size,
+            (NodeKind::Dir(children), NodeKind::Dir(other_children)) => {
+                children.len() == other_children.len()
+                    && children.iter().all(|(name, &child)| {
+                        other_children
+                            .get(name)
+                            .is_some_and(|&other_child| self.same_tree(child, other, other_child))
+                    })
+            }
+            _ => false,
+        }
     }
 }
 
-impl From<&str> for Filesystem {
-    fn from(input: &str) -> Self {
-        let mut fs = Filesystem::new_dir(HashMap::new());
-        let mut working_dir: Vec<String> = vec![];
-
-        input.lines().for_each(|line| {
-            if let Some((_, command)) = line.split_once("$ ") {
-                if let Some((_, go_to)) = command.split_once("cd ") {
-                    match go_to {
-                        "/" => {
-                            working_dir = vec![];
-                        }
-                        ".." => {
-                            working_dir.pop();
-                        }
-                        subdir => {
-                            working_dir.push(subdir.to_string());
-                        }
-                    }
+impl PartialEq for Filesystem {
+    /// Filesystems are equal if they hold the same tree, regardless of insertion order
+    fn eq(&self, other: &Self) -> bool {
+        self.same_tree(ROOT, other, ROOT)
+    }
+}
+
+/// Something in a terminal session that doesn't add up
+#[derive(Debug, PartialEq)]
+enum ShellErrorKind {
+    UnknownCommand(String),
+    /// A line of command output that doesn't follow an `ls`
+    UnexpectedOutput,
+    InvalidListing(String),
+    InvalidSize(String),
+    /// A file or directory name that's empty, `.` or `..`, or contains a `/`
+    InvalidName(String),
+    /// A file listed again with a different size
+    ConflictingSize {
+        path: FsPath,
+        previous: u64,
+        listed: u64,
+    },
+    /// An entry seen both as a file and as a directory
+    ConflictingKind(FsPath),
+    NotADirectory(FsPath),
+    AboveRoot,
+    /// A directory's total size no longer fits in 64 bits
+    SizeOverflow(FsPath),
+}
+
+/// An invalid line in a terminal session, with its 1-based line number
+#[derive(Debug, PartialEq)]
+struct ShellError {
+    line: usize,
+    kind: ShellErrorKind,
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ShellErrorKind::UnknownCommand(command) => write!(f, "unknown command \"{}\"", command),
+            ShellErrorKind::UnexpectedOutput => write!(f, "output without a preceding `ls`"),
+            ShellErrorKind::InvalidListing(line) => write!(f, "invalid `ls` output \"{}\"", line),
+            ShellErrorKind::InvalidSize(size) => write!(f, "invalid file size \"{}\"", size),
+            ShellErrorKind::InvalidName(name) => write!(f, "invalid name \"{}\"", name),
+            ShellErrorKind::ConflictingSize {
+                path,
+                previous,
+                listed,
+            } => write!(
+                f,
+                "{} listed with size {}, previously {}",
+                path, listed, previous
+            ),
+            ShellErrorKind::ConflictingKind(path) => {
+                write!(f, "{} listed as both a file and a directory", path)
+            }
+            ShellErrorKind::NotADirectory(path) => write!(f, "{} is not a directory", path),
+            ShellErrorKind::AboveRoot => write!(f, "`cd ..` above the root directory"),
+            ShellErrorKind::SizeOverflow(path) => write!(f, "total size of {} overflows", path),
+        }
+    }
+}
+
+impl std::error::Error for ShellError {}
+
+/// Replays a terminal session line by line, building up the filesystem it explores
+struct Shell {
+    fs: Filesystem,
+    working_dir: NodeId,
+    /// Whether the last command was an `ls`, so output lines are expected
+    listing: bool,
+}
+
+impl Shell {
+    fn new() -> Shell {
+        Shell {
+            fs: Filesystem::new(),
+            working_dir: ROOT,
+            listing: false,
+        }
+    }
+
+    fn cd(&mut self, target: &str) -> Result<(), ShellErrorKind> {
+        self.working_dir = match target {
+            "/" => ROOT,
+            ".." => self
+                .fs
+                .parent(self.working_dir)
+                .ok_or(ShellErrorKind::AboveRoot)?,
+            name => match self.fs.child(self.working_dir, name) {
+                Some(file) if !self.fs.is_dir(file) => {
+                    return Err(ShellErrorKind::NotADirectory(self.fs.path_of(file)))
                 }
-            } else {
-                // Listing some directory contents
-                let (info, name) = line.split_once(' ').expect("Invalid ls line");
-                match info {
-                    "dir" => {
-                        fs.insert_at(
-                            &working_dir,
-                            name.to_string(),
-                            Filesystem::new_dir(HashMap::new()),
-                        );
-                    }
-                    size => {
-                        fs.insert_at(
-                            &working_dir,
-                            name.to_string(),
-                            Filesystem::new_file(size.parse().expect("Invalid filesize")),
-                        );
-                    }
+                // a directory we `cd` into must exist, even if it was never listed.
+                _ => self.fs.insert_dir(self.working_dir, name)?,
+            },
+        };
+
+        Ok(())
+    }
+
+    fn run_line(&mut self, line: &str) -> Result<(), ShellErrorKind> {
+        if let Some(command) = line.strip_prefix("$ ") {
+            self.listing = false;
+            return match command.split_once(' ') {
+                Some(("cd", target)) => self.cd(target),
+                None if command == "ls" => {
+                    self.listing = true;
+                    Ok(())
                 }
+                _ => Err(ShellErrorKind::UnknownCommand(command.to_string())),
+            };
+        }
+
+        if !self.listing {
+            return Err(ShellErrorKind::UnexpectedOutput);
+        }
+
+        match line.split_once(' ') {
+            Some(("dir", name)) => self.fs.insert_dir(self.working_dir, name).map(|_| ()),
+            Some((size, name)) => {
+                let size = size
+                    .parse()
+                    .map_err(|_| ShellErrorKind::InvalidSize(size.to_string()))?;
+                self.fs
+                    .insert_file(self.working_dir, name, size)
+                    .map(|_| ())
             }
-        });
+            None => Err(ShellErrorKind::InvalidListing(line.to_string())),
+        }
+    }
+}
 
-        fs
+impl Filesystem {
+    /// Reconstruct a filesystem from a terminal session of `cd` and `ls` commands
+    fn from_transcript(input: &str) -> Result<Filesystem, ShellError> {
+        let mut shell = Shell::new();
+
+        input.lines().enumerate().try_for_each(|(index, line)| {
+            shell.run_line(line).map_err(|kind| ShellError {
+                line: index + 1,
+                kind,
+            })
+        })?;
+
+        Ok(shell.fs)
+    }
+}
+
+impl From<&str> for Filesystem {
+    fn from(input: &str) -> Self {
+        Filesystem::from_transcript(input).unwrap_or_else(|e| panic!("Invalid transcript: {}", e))
     }
 }
 
@@ -179,41 +403,532 @@ impl From<String> for Filesystem {
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let filesystem = Filesystem::from(input);
-    Some(
-        filesystem
+/// A way of presenting the reconstructed filesystem
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Report {
+    /// Indented listing of every entry with its size, like the puzzle description
+    Tree,
+    /// Directories by total size, largest first, like `du -h | sort -rh`
+    Du,
+    /// The largest N files
+    Largest(usize),
+    /// What to delete to make room for the update
+    Cleanup,
+}
+
+/// Format a size like `du -h`: 1024-based units, rounded up, one decimal below 10
+fn human_size(size: u64) -> String {
+    let mut value = size as f64;
+    let mut unit = "";
+    for next_unit in ["K", "M", "G", "T", "P", "E"] {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next_unit;
+    }
+
+    if unit.is_empty() {
+        size.to_string()
+    } else if value < 10.0 {
+        format!("{:.1}{}", (value * 10.0).ceil() / 10.0, unit)
+    } else {
+        format!("{}{}", value.ceil(), unit)
+    }
+}
+
+impl Filesystem {
+    fn render_tree(&self) -> String {
+        let mut lines = vec![];
+        let mut stack = vec![(ROOT, 0)];
+
+        while let Some((id, depth)) = stack.pop() {
+            let node = &self.nodes[id];
+            let (name, kind) = match (&node.kind, id) {
+                (_, ROOT) => ("/", "dir"),
+                (NodeKind::Dir(_), _) => (node.name.as_str(), "dir"),
+                (NodeKind::File, _) => (node.name.as_str(), "file"),
+            };
+            lines.push(format!(
+                "{}- {} ({}, size={})",
+                "  ".repeat(depth),
+                name,
+                kind,
+                node.size
+            ));
+
+            let mut children = self.children(id).collect::<Vec<_>>();
+            children.reverse();
+            stack.extend(children.into_iter().map(|child| (child, depth + 1)));
+        }
+
+        lines.join("\n")
+    }
+
+    fn render_du(&self) -> String {
+        let mut dirs = self.directories().collect::<Vec<_>>();
+        dirs.sort_by_key(|&id| std::cmp::Reverse(self.total_size(id)));
+
+        dirs.iter()
+            .map(|&id| format!("{}\t{}", human_size(self.total_size(id)), self.path_of(id)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render_largest(&self, count: usize) -> String {
+        let mut files = self
             .walk()
+            .into_iter()
+            .filter(|&id| !self.is_dir(id))
+            .collect::<Vec<_>>();
+        files.sort_by_key(|&id| std::cmp::Reverse(self.total_size(id)));
+
+        files
+            .iter()
+            .take(count)
+            .map(|&id| format!("{}\t{}", self.total_size(id), self.path_of(id)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render_cleanup(&self, planner: &CleanupPlanner) -> String {
+        let describe = |plan: Result<CleanupPlan, CleanupError>| match plan {
+            Ok(plan) if plan.directories.is_empty() => "nothing to delete".to_string(),
+            Ok(plan) => format!(
+                "{} (frees {} of {} needed{})",
+                plan.directories
+                    .iter()
+                    .map(|&id| self.path_of(id).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                plan.freed,
+                plan.needed,
+                if plan.exact { "" } else { ", approximate" }
+            ),
+            Err(e) => format!("impossible, {}", e),
+        };
+
+        format!(
+            "smallest directory: {}\nsmallest set: {}",
+            describe(planner.smallest_directory(self)),
+            describe(planner.smallest_set(self))
+        )
+    }
+
+    fn render(&self, report: Report) -> String {
+        match report {
+            Report::Tree => self.render_tree(),
+            Report::Du => self.render_du(),
+            Report::Largest(count) => self.render_largest(count),
+            Report::Cleanup => self.render_cleanup(&CleanupPlanner {
+                capacity: DISK_CAPACITY,
+                required_free: REQUIRED_FREE,
+            }),
+        }
+    }
+}
+
+impl Filesystem {
+    /// Write a terminal session of `cd` and `ls` commands that reconstructs this filesystem
+    fn to_transcript(&self) -> String {
+        let mut lines = vec!["$ cd /".to_string()];
+        self.write_listing(ROOT, &mut lines);
+        lines.join("\n")
+    }
+
+    fn write_listing(&self, dir: NodeId, lines: &mut Vec<String>) {
+        lines.push("$ ls".to_string());
+        self.children(dir).for_each(|id| {
+            let node = &self.nodes[id];
+            match node.kind {
+                NodeKind::Dir(_) => lines.push(format!("dir {}", node.name)),
+                NodeKind::File => lines.push(format!("{} {}", node.size, node.name)),
+            }
+        });
+
+        self.children(dir)
+            .filter(|&id| self.is_dir(id))
+            .for_each(|id| {
+                lines.push(format!("$ cd {}", self.nodes[id].name));
+                self.write_listing(id, lines);
+                lines.push("$ cd ..".to_string());
+            });
+    }
+
+    /// Build a pseudo-random filesystem with the given number of entries, for stress testing.
+    /// Files hold 1 to `max_file_size` bytes, or nothing if that's zero.
+    /// The same seed always gives the same tree.
+    fn random(seed: u64, entries: usize, max_file_size: u64) -> Filesystem {
+        // xorshift64, which must not start at zero
+        let mut state = (seed ^ 0x9E37_79B9_7F4A_7C15).max(1);
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut fs = Filesystem::new();
+        let mut dirs = vec![ROOT];
+
+        for index in 0..entries {
+            let parent = dirs[next() as usize % dirs.len()];
+            let name_len = 1 + next() as usize % 8;
+            let name = (0..name_len)
+                .map(|_| (b'a' + (next() % 26) as u8) as char)
+                .chain(format!(".{}", index).chars())
+                .collect::<String>();
+
+            if next() % 3 == 0 {
+                dirs.push(fs.insert_dir(parent, &name).expect("Names are unique"));
+            } else {
+                let size = match max_file_size {
+                    0 => 0,
+                    max => 1 + next() % max,
+                };
+                fs.insert_file(parent, &name, size)
+                    .expect("Names are unique");
+            }
+        }
+
+        fs
+    }
+}
+
+/// Size of the device's disk
+const DISK_CAPACITY: u64 = 70000000;
+
+/// Free space the update needs
+const REQUIRED_FREE: u64 = 30000000;
+
+/// Disk constraints for freeing up space before an update
+struct CleanupPlanner {
+    capacity: u64,
+    required_free: u64,
+}
+
+/// Directories to delete, none of which is inside another
+#[derive(Debug, PartialEq)]
+struct CleanupPlan {
+    directories: Vec<NodeId>,
+    /// Bytes that still had to be freed before deleting anything
+    needed: u64,
+    freed: u64,
+    /// Whether nothing frees less, or the plan may be a little over the best
+    exact: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum CleanupError {
+    /// The filesystem holds more than the disk can
+    OverCapacity { used: u64, capacity: u64 },
+    /// More free space is required than the disk has in total
+    Unsatisfiable { required_free: u64, capacity: u64 },
+}
+
+impl fmt::Display for CleanupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CleanupError::OverCapacity { used, capacity } => {
+                write!(f, "{} bytes used on a {} byte disk", used, capacity)
+            }
+            CleanupError::Unsatisfiable {
+                required_free,
+                capacity,
+            } => write!(
+                f,
+                "{} bytes required free on a {} byte disk",
+                required_free, capacity
+            ),
+        }
+    }
+}
+
+/// Most ways of freeing space a directory may have before `smallest_set` stops looking for
+/// the exact answer and groups amounts into buckets instead
+const MAX_EXACT_OPTIONS: usize = 1024;
+
+/// Amounts below what's needed are grouped into at most this many buckets when there are too
+/// many ways of freeing space to try them all
+const CLEANUP_BUCKETS: u64 = 128;
+
+/// A bucket of bytes freed, and whether the slot keeps its most bytes rather than its fewest
+type Slot = (u64, bool);
+
+/// Slot holding the fewest bytes freed at or above what's needed
+const ENOUGH: Slot = (u64::MAX, false);
+
+/// Ways of freeing space inside one directory, at most two for each bucket of bytes freed
+#[derive(Clone)]
+struct Options {
+    needed: u64,
+    /// Bytes covered by each bucket below `needed`, where 1 keeps every amount apart
+    width: u64,
+    /// Bytes freed and the directories that free them. Each bucket below `needed` has a pair of
+    /// slots keeping its fewest and most bytes freed, and `ENOUGH` keeps the fewest bytes at
+    /// or above `needed`.
+    slots: BTreeMap<Slot, (u64, Vec<NodeId>)>,
+}
+
+impl Options {
+    /// Every amount below `needed` kept apart, so nothing is lost
+    fn exact(needed: u64) -> Options {
+        Options {
+            needed,
+            width: 1,
+            slots: BTreeMap::new(),
+        }
+    }
+
+    /// Amounts below `needed` grouped into `CLEANUP_BUCKETS` buckets
+    fn bucketed(needed: u64) -> Options {
+        Options {
+            width: needed.div_ceil(CLEANUP_BUCKETS).max(1),
+            ..Options::exact(needed)
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Keep a way of freeing space wherever it beats what its bucket holds, preferring fewer
+    /// directories on ties. `dirs` is only called for slots it's kept in.
+    fn keep(&mut self, freed: u64, count: usize, dirs: impl Fn() -> Vec<NodeId>) {
+        let bucket = freed / self.width;
+        let slots = if freed >= self.needed {
+            vec![ENOUGH]
+        } else if self.width == 1 {
+            vec![(bucket, false)]
+        } else {
+            vec![(bucket, false), (bucket, true)]
+        };
+
+        for slot in slots {
+            let fewest = !slot.1;
+            let better = match self.slots.get(&slot) {
+                None => true,
+                Some((known, _)) if *known != freed => fewest == (freed < *known),
+                Some((_, known_dirs)) => count < known_dirs.len(),
+            };
+            if better {
+                self.slots.insert(slot, (freed, dirs()));
+            }
+        }
+    }
+
+    /// Every way kept, skipping a bucket's most bytes freed when it's also the fewest
+    fn iter(&self) -> impl Iterator<Item = &(u64, Vec<NodeId>)> {
+        self.slots
             .iter()
-            .map(|loc| filesystem.get_at(loc).expect("Invalid location"))
-            .filter(|fs| fs.is_dir())
-            .map(|fs| fs.total_size())
-            .filter(|&size| size <= 100000)
-            .sum(),
-    )
+            .filter(|&(&(bucket, most), (freed, _))| {
+                !most
+                    || self
+                        .slots
+                        .get(&(bucket, false))
+                        .is_none_or(|(fewest, _)| fewest != freed)
+            })
+            .map(|(_, option)| option)
+    }
+
+    /// The least freeing that's enough
+    fn best(&self) -> Option<&(u64, Vec<NodeId>)> {
+        self.slots.get(&ENOUGH)
+    }
+}
+
+impl CleanupPlanner {
+    /// Bytes that have to be freed, without underflowing on small or overfull disks
+    fn space_needed(&self, fs: &Filesystem) -> Result<u64, CleanupError> {
+        let used = fs.total_size(ROOT);
+        let free = self
+            .capacity
+            .checked_sub(used)
+            .ok_or(CleanupError::OverCapacity {
+                used,
+                capacity: self.capacity,
+            })?;
+
+        if self.required_free > self.capacity {
+            return Err(CleanupError::Unsatisfiable {
+                required_free: self.required_free,
+                capacity: self.capacity,
+            });
+        }
+
+        Ok(self.required_free.saturating_sub(free))
+    }
+
+    /// Delete the single smallest directory that frees enough space
+    fn smallest_directory(&self, fs: &Filesystem) -> Result<CleanupPlan, CleanupError> {
+        let needed = self.space_needed(fs)?;
+        if needed == 0 {
+            return Ok(CleanupPlan {
+                directories: vec![],
+                needed,
+                freed: 0,
+                exact: true,
+            });
+        }
+
+        // the root always qualifies, since the disk can hold the required free space.
+        let dir = fs
+            .directories()
+            .filter(|&id| fs.total_size(id) >= needed)
+            .min_by_key(|&id| fs.total_size(id))
+            .unwrap_or(ROOT);
+
+        Ok(CleanupPlan {
+            directories: vec![dir],
+            needed,
+            freed: fs.total_size(dir),
+            exact: true,
+        })
+    }
+
+    /// Delete the set of non-nested directories that frees enough space while freeing the least,
+    /// preferring fewer directories on ties. When a directory has more than `MAX_EXACT_OPTIONS`
+    /// ways of freeing space this groups amounts into buckets instead, and the plan is marked
+    /// as not exact: each bucket merged along the way can cost up to one bucket's width.
+    fn smallest_set(&self, fs: &Filesystem) -> Result<CleanupPlan, CleanupError> {
+        let needed = self.space_needed(fs)?;
+        let (options, exact) =
+            match self.options(fs, ROOT, &Options::exact(needed), MAX_EXACT_OPTIONS) {
+                Some(options) => (options, true),
+                None => {
+                    let options = self.options(fs, ROOT, &Options::bucketed(needed), usize::MAX);
+                    (options.expect("Bucketed options have no limit"), false)
+                }
+            };
+
+        let (freed, directories) = options
+            .best()
+            .cloned()
+            .expect("Deleting the root always frees enough");
+
+        Ok(CleanupPlan {
+            directories,
+            needed,
+            freed,
+            exact,
+        })
+    }
+
+    /// Combine the ways of freeing space in each subdirectory, or delete this directory outright.
+    /// Gives up if any directory ends up with more than `limit` ways.
+    fn options(
+        &self,
+        fs: &Filesystem,
+        dir: NodeId,
+        empty: &Options,
+        limit: usize,
+    ) -> Option<Options> {
+        let mut options = empty.clone();
+        options.keep(0, 0, Vec::new);
+
+        for child in fs.children(dir).filter(|&id| fs.is_dir(id)) {
+            let child_options = self.options(fs, child, empty, limit)?;
+            let mut combined = empty.clone();
+            for (freed, dirs) in options.iter() {
+                for (child_freed, child_dirs) in child_options.iter() {
+                    combined.keep(freed + child_freed, dirs.len() + child_dirs.len(), || {
+                        [&dirs[..], child_dirs].concat()
+                    });
+                }
+                if combined.len() > limit {
+                    return None;
+                }
+            }
+            options = combined;
+        }
+        options.keep(fs.total_size(dir), 1, || vec![dir]);
+
+        Some(options)
+    }
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
+pub fn part_one(input: &str) -> Option<u64> {
     let filesystem = Filesystem::from(input);
+    let total = filesystem
+        .directories()
+        .map(|id| filesystem.total_size(id))
+        .filter(|&size| size <= 100000)
+        .try_fold(0_u64, |total, size| total.checked_add(size));
 
-    let space_needed = filesystem.total_size() - 40000000;
-    println!("Space Needed: {}", space_needed);
+    total
+}
 
-    let mut candidates = filesystem
-        .walk()
-        .iter()
-        .map(|loc| filesystem.get_at(loc).expect("Invalid location"))
-        .filter(|fs| fs.is_dir())
-        .map(|fs| fs.total_size())
-        .filter(|&size| size >= space_needed)
-        .collect::<Vec<_>>();
+pub fn part_two(input: &str) -> Option<u64> {
+    let filesystem = Filesystem::from(input);
+    let planner = CleanupPlanner {
+        capacity: DISK_CAPACITY,
+        required_free: REQUIRED_FREE,
+    };
+
+    planner
+        .smallest_directory(&filesystem)
+        .ok()
+        .map(|plan| plan.freed)
+}
 
-    candidates.sort();
-    candidates.first().copied()
+struct Args {
+    report: Option<Report>,
+    /// Number of entries in a random filesystem to print as a transcript
+    generate: Option<usize>,
+    seed: u64,
+}
+
+/// Parse `--report <tree|du|largest|cleanup>` with `--top <N>` (for `largest`, default 10),
+/// or `--generate <entries>` with an optional `--seed <N>`
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    let top = args.opt_value_from_str("--top")?.unwrap_or(10);
+    let style: Option<String> = args.opt_value_from_str("--report")?;
+
+    let report = style
+        .map(|style| match style.as_str() {
+            "tree" => Ok(Report::Tree),
+            "du" => Ok(Report::Du),
+            "largest" => Ok(Report::Largest(top)),
+            "cleanup" => Ok(Report::Cleanup),
+            _ => Err(pico_args::Error::Utf8ArgumentParsingFailed {
+                cause: "expected tree, du, largest or cleanup".to_string(),
+                value: style,
+            }),
+        })
+        .transpose()?;
+
+    Ok(Args {
+        report,
+        generate: args.opt_value_from_str("--generate")?,
+        seed: args.opt_value_from_str("--seed")?.unwrap_or(7),
+    })
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(entries) = args.generate {
+        println!(
+            "{}",
+            Filesystem::random(args.seed, entries, 300000).to_transcript()
+        );
+        return;
+    }
+
     let input = &advent_of_code::read_file("inputs", 7);
+
+    if let Some(report) = args.report {
+        println!("{}", Filesystem::from(&input[..]).render(report));
+        return;
+    }
+
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -221,89 +936,64 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use rstest::rstest;
 
     fn example_filesystem() -> Filesystem {
-        Filesystem::new_dir(HashMap::from([
-            (
-                "a".to_string(),
-                Filesystem::new_dir(HashMap::from([
-                    (
-                        "e".to_string(),
-                        Filesystem::new_dir(HashMap::from([(
-                            "i".to_string(),
-                            Filesystem::new_file(584),
-                        )])),
-                    ),
-                    ("f".to_string(), Filesystem::new_file(29116)),
-                    ("g".to_string(), Filesystem::new_file(2557)),
-                    ("h.lst".to_string(), Filesystem::new_file(62596)),
-                ])),
-            ),
-            ("b.txt".to_string(), Filesystem::new_file(14848514)),
-            ("c.dat".to_string(), Filesystem::new_file(8504156)),
-            (
-                "d".to_string(),
-                Filesystem::new_dir(HashMap::from([
-                    ("j".to_string(), Filesystem::new_file(4060174)),
-                    ("d.log".to_string(), Filesystem::new_file(8033020)),
-                    ("d.ext".to_string(), Filesystem::new_file(5626152)),
-                    ("k".to_string(), Filesystem::new_file(7214296)),
-                ])),
-            ),
-        ]))
+        let mut fs = Filesystem::new();
+        [
+            ("/a/e/i", 584),
+            ("/a/f", 29116),
+            ("/a/g", 2557),
+            ("/a/h.lst", 62596),
+            ("/b.txt", 14848514),
+            ("/c.dat", 8504156),
+            ("/d/j", 4060174),
+            ("/d/d.log", 8033020),
+            ("/d/d.ext", 5626152),
+            ("/d/k", 7214296),
+        ]
+        .iter()
+        .for_each(|&(path, size)| {
+            fs.add_file(&FsPath::from(path), size);
+        });
+        fs
+    }
+
+    #[rstest]
+    #[case("/", FsPath(vec![]))]
+    #[case("/a/e", FsPath(vec!["a".to_string(), "e".to_string()]))]
+    fn test_fs_path(#[case] input: &str, #[case] path: FsPath) {
+        assert_eq!(FsPath::from(input), path);
+        assert_eq!(path.to_string(), input);
     }
 
     #[test]
-    fn test_insert_at() {
+    fn test_insert_file() {
         let mut fs = example_filesystem();
-        fs.insert_at(
-            &vec!["a".to_string(), "e".to_string()],
-            "kevin.txt".to_string(),
-            Filesystem::new_file(1234),
-        );
+        let dir = fs.get_at(&FsPath::from("/a/e")).unwrap();
+        let file = fs.insert_file(dir, "kevin.txt", 1234).unwrap();
 
-        assert_eq!(
-            fs,
-            Filesystem::new_dir(HashMap::from([
-                (
-                    "a".to_string(),
-                    Filesystem::new_dir(HashMap::from([
-                        (
-                            "e".to_string(),
-                            Filesystem::new_dir(HashMap::from([
-                                ("i".to_string(), Filesystem::new_file(584),),
-                                ("kevin.txt".to_string(), Filesystem::new_file(1234),),
-                            ])),
-                        ),
-                        ("f".to_string(), Filesystem::new_file(29116)),
-                        ("g".to_string(), Filesystem::new_file(2557)),
-                        ("h.lst".to_string(), Filesystem::new_file(62596)),
-                    ])),
-                ),
-                ("b.txt".to_string(), Filesystem::new_file(14848514)),
-                ("c.dat".to_string(), Filesystem::new_file(8504156)),
-                (
-                    "d".to_string(),
-                    Filesystem::new_dir(HashMap::from([
-                        ("j".to_string(), Filesystem::new_file(4060174)),
-                        ("d.log".to_string(), Filesystem::new_file(8033020)),
-                        ("d.ext".to_string(), Filesystem::new_file(5626152)),
-                        ("k".to_string(), Filesystem::new_file(7214296)),
-                    ])),
-                ),
-            ]))
-        )
+        assert_eq!(fs.path_of(file), FsPath::from("/a/e/kevin.txt"));
+        assert_eq!(fs.parent(file), Some(dir));
+        assert_eq!(fs.total_size(dir), 584 + 1234);
+        assert_eq!(fs.total_size(ROOT), 48381165 + 1234);
+
+        let mut expected = example_filesystem();
+        expected.add_file(&FsPath::from("/a/e/kevin.txt"), 1234);
+        assert_eq!(fs, expected);
+        assert_ne!(fs, example_filesystem());
     }
 
     #[rstest]
-    #[case(vec!["a".to_string(), "e".to_string()], 584)]
-    #[case(vec!["a".to_string()], 94853)]
-    #[case(vec!["d".to_string()], 24933642)]
-    #[case(vec![], 48381165)]
-    fn test_total_size(#[case] loc: Vec<String>, #[case] expected_size: u32) {
+    #[case("/a/e", 584)]
+    #[case("/a", 94853)]
+    #[case("/d", 24933642)]
+    #[case("/", 48381165)]
+    fn test_total_size(#[case] path: &str, #[case] expected_size: u64) {
+        let fs = example_filesystem();
         assert_eq!(
-            example_filesystem().get_at(&loc).unwrap().total_size(),
+            fs.total_size(fs.get_at(&FsPath::from(path)).unwrap()),
             expected_size
         )
     }
@@ -314,28 +1004,494 @@ mod tests {
         assert_eq!(Filesystem::from(input), example_filesystem());
     }
 
+    #[test]
+    fn test_cd_into_unlisted_dir() {
+        let fs = Filesystem::from("$ cd a\n$ cd b\n$ ls\n12 c.txt\n$ cd /\n$ ls\ndir a\n3 d");
+
+        let mut expected = Filesystem::new();
+        expected.add_file(&FsPath::from("/a/b/c.txt"), 12);
+        expected.add_file(&FsPath::from("/d"), 3);
+        assert_eq!(fs, expected);
+    }
+
+    #[test]
+    fn test_repeated_listing() {
+        let input = "$ ls\n10 a\ndir b\n$ cd b\n$ cd ..\n$ ls\ndir b\n10 a";
+        assert_eq!(
+            Filesystem::from_transcript(input).unwrap().total_size(ROOT),
+            10
+        );
+    }
+
+    #[rstest]
+    #[case("$ ls\n10 a\n$ ls\n20 a", 4, ShellErrorKind::ConflictingSize { path: FsPath::from("/a"), previous: 10, listed: 20 })]
+    #[case(
+        "$ ls\n10 a\ndir a",
+        3,
+        ShellErrorKind::ConflictingKind(FsPath::from("/a"))
+    )]
+    #[case(
+        "$ ls\ndir a\n$ cd a\n$ ls\n5 b\n$ cd b",
+        6,
+        ShellErrorKind::NotADirectory(FsPath::from("/a/b"))
+    )]
+    #[case("$ cd /\n$ cd ..", 2, ShellErrorKind::AboveRoot)]
+    #[case("$ cd a\n10 b", 2, ShellErrorKind::UnexpectedOutput)]
+    #[case("$ rm -rf /", 1, ShellErrorKind::UnknownCommand("rm -rf /".to_string()))]
+    #[case("$ ls\nbig a", 2, ShellErrorKind::InvalidSize("big".to_string()))]
+    #[case("$ ls\nfile", 2, ShellErrorKind::InvalidListing("file".to_string()))]
+    #[case("$ cd ", 1, ShellErrorKind::InvalidName("".to_string()))]
+    #[case("$ cd a/b", 1, ShellErrorKind::InvalidName("a/b".to_string()))]
+    #[case("$ ls\ndir x/y", 2, ShellErrorKind::InvalidName("x/y".to_string()))]
+    #[case("$ ls\n10 a\n123 ", 3, ShellErrorKind::InvalidName("".to_string()))]
+    #[case("$ ls\ndir ..\n10 x", 2, ShellErrorKind::InvalidName("..".to_string()))]
+    #[case("$ ls\ndir .", 2, ShellErrorKind::InvalidName(".".to_string()))]
+    #[case("$ ls\n10 ..", 2, ShellErrorKind::InvalidName("..".to_string()))]
+    #[case("$ cd .", 1, ShellErrorKind::InvalidName(".".to_string()))]
+    fn test_transcript_errors(
+        #[case] input: &str,
+        #[case] line: usize,
+        #[case] kind: ShellErrorKind,
+    ) {
+        assert_eq!(
+            Filesystem::from_transcript(input),
+            Err(ShellError { line, kind })
+        );
+    }
+
+    #[test]
+    fn test_error_display() {
+        let error = Filesystem::from_transcript("$ ls\n10 a\n$ cd a").unwrap_err();
+        assert_eq!(error.to_string(), "line 3: /a is not a directory");
+    }
+
     #[test]
     fn test_fs_walk() {
+        let fs = example_filesystem();
+        assert_eq!(
+            fs.walk()
+                .into_iter()
+                .map(|id| fs.path_of(id).to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "/", "/a", "/a/e", "/a/e/i", "/a/f", "/a/g", "/a/h.lst", "/b.txt", "/c.dat", "/d",
+                "/d/d.ext", "/d/d.log", "/d/j", "/d/k",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_tree() {
+        assert_eq!(
+            example_filesystem().render(Report::Tree),
+            "\
+- / (dir, size=48381165)
+  - a (dir, size=94853)
+    - e (dir, size=584)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir, size=24933642)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)"
+        );
+    }
+
+    #[test]
+    fn test_render_du() {
         assert_eq!(
-            example_filesystem().walk(),
-            HashSet::from([
-                vec![],
-                vec!["c.dat".to_string()],
-                vec!["b.txt".to_string()],
-                vec!["a".to_string()],
-                vec!["a".to_string(), "h.lst".to_string()],
-                vec!["a".to_string(), "e".to_string()],
-                vec!["a".to_string(), "e".to_string(), "i".to_string()],
-                vec!["a".to_string(), "f".to_string()],
-                vec!["a".to_string(), "g".to_string()],
-                vec!["d".to_string()],
-                vec!["d".to_string(), "d.ext".to_string()],
-                vec!["d".to_string(), "j".to_string()],
-                vec!["d".to_string(), "k".to_string()],
-                vec!["d".to_string(), "d.log".to_string()]
-            ])
+            example_filesystem().render(Report::Du),
+            "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e"
         );
     }
+
+    #[test]
+    fn test_render_largest() {
+        assert_eq!(
+            example_filesystem().render(Report::Largest(3)),
+            "14848514\t/b.txt\n8504156\t/c.dat\n8033020\t/d/d.log"
+        );
+    }
+
+    #[test]
+    fn test_render_cleanup() {
+        assert_eq!(
+            example_filesystem().render(Report::Cleanup),
+            "smallest directory: /d (frees 24933642 of 8381165 needed)\n\
+             smallest set: /d (frees 24933642 of 8381165 needed)"
+        );
+        assert_eq!(
+            example_filesystem().render_cleanup(&CleanupPlanner {
+                capacity: DISK_CAPACITY,
+                required_free: 1000,
+            }),
+            "smallest directory: nothing to delete\nsmallest set: nothing to delete"
+        );
+    }
+
+    #[rstest]
+    #[case(0, "0")]
+    #[case(1023, "1023")]
+    #[case(1024, "1.0K")]
+    #[case(1536, "1.5K")]
+    #[case(10240, "10K")]
+    #[case(94853, "93K")]
+    #[case(4294967295, "4.0G")]
+    #[case(u64::MAX, "16E")]
+    fn test_human_size(#[case] size: u64, #[case] expected: &str) {
+        assert_eq!(human_size(size), expected);
+    }
+
+    fn paths(fs: &Filesystem, plan: &CleanupPlan) -> Vec<String> {
+        plan.directories
+            .iter()
+            .map(|&id| fs.path_of(id).to_string())
+            .collect()
+    }
+
+    #[rstest]
+    #[case(70000000, 30000000, vec!["/d"], 24933642)]
+    #[case(48381165 + 100000, 100000 + 94853, vec!["/a"], 94853)]
+    #[case(48381165 + 100000, 100000 + 584, vec!["/a/e"], 584)]
+    #[case(48381165, 48381165, vec!["/"], 48381165)]
+    #[case(70000000, 1000, vec![], 0)]
+    fn test_smallest_directory(
+        #[case] capacity: u64,
+        #[case] required_free: u64,
+        #[case] expected_paths: Vec<&str>,
+        #[case] expected_freed: u64,
+    ) {
+        let fs = example_filesystem();
+        let plan = CleanupPlanner {
+            capacity,
+            required_free,
+        }
+        .smallest_directory(&fs)
+        .unwrap();
+
+        assert_eq!(paths(&fs, &plan), expected_paths);
+        assert_eq!(plan.freed, expected_freed);
+    }
+
+    #[test]
+    fn test_smallest_set() {
+        // /a and /a/e are nested, so 94853 + 584 is not on the table
+        let mut fs = example_filesystem();
+        fs.add_file(&FsPath::from("/x/y"), 100);
+        fs.add_file(&FsPath::from("/z/w"), 500);
+        let used = fs.total_size(ROOT);
+
+        let planner = |needed: u64| CleanupPlanner {
+            capacity: used,
+            required_free: needed,
+        };
+
+        let plan = planner(600).smallest_set(&fs).unwrap();
+        assert_eq!(paths(&fs, &plan), vec!["/x", "/z"]);
+        assert_eq!(plan.freed, 600);
+
+        let plan = planner(1000).smallest_set(&fs).unwrap();
+        assert_eq!(paths(&fs, &plan), vec!["/a/e", "/z"]);
+        assert_eq!(plan.freed, 1084);
+
+        let plan = planner(1200).smallest_set(&fs).unwrap();
+        assert_eq!(paths(&fs, &plan), vec!["/a"]);
+        assert_eq!(plan.freed, 94853);
+
+        // a single directory is never better than the best set
+        let single = planner(1200).smallest_directory(&fs).unwrap();
+        assert!(single.freed >= plan.freed);
+    }
+
+    /// The least a set of non-nested directories can free while freeing at least `needed`,
+    /// found by trying every set
+    fn brute_force_smallest_set(fs: &Filesystem, needed: u64) -> u64 {
+        let dirs = fs.directories().collect::<Vec<_>>();
+        let inside = |id: NodeId, ancestor: NodeId| {
+            std::iter::successors(fs.parent(id), |&parent| fs.parent(parent))
+                .any(|parent| parent == ancestor)
+        };
+
+        (0..1_u32 << dirs.len())
+            .filter_map(|set| {
+                let chosen = (0..dirs.len())
+                    .filter(|index| set & (1 << index) != 0)
+                    .map(|index| dirs[index])
+                    .collect::<Vec<_>>();
+                let nested = chosen
+                    .iter()
+                    .any(|&id| chosen.iter().any(|&other| inside(id, other)));
+                let freed = chosen.iter().map(|&id| fs.total_size(id)).sum::<u64>();
+                (!nested && freed >= needed).then_some(freed)
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case(66, 3)]
+    #[case(1, 2)]
+    #[case(2, 3)]
+    #[case(7, 4)]
+    #[case(12, 5)]
+    #[case(30, 3)]
+    #[case(41, 6)]
+    #[case(99, 10)]
+    fn test_smallest_set_matches_brute_force(#[case] seed: u64, #[case] fraction: u64) {
+        let fs = Filesystem::random(seed, 40, 300000);
+        assert!(fs.directories().count() <= 18);
+        let used = fs.total_size(ROOT);
+        let plan = CleanupPlanner {
+            capacity: used,
+            required_free: used / fraction,
+        }
+        .smallest_set(&fs)
+        .unwrap();
+
+        assert!(plan.exact);
+        assert_eq!(plan.freed, brute_force_smallest_set(&fs, plan.needed));
+    }
+
+    #[test]
+    fn test_smallest_set_on_large_tree() {
+        // far too many ways of freeing space on a tree this size to try them all
+        let fs = Filesystem::random(5, 500, 300000);
+        assert_eq!(fs.directories().count(), 149);
+        let used = fs.total_size(ROOT);
+        let planner = CleanupPlanner {
+            capacity: used + used / 10,
+            required_free: used / 10 + used / 15,
+        };
+
+        let plan = planner.smallest_set(&fs).unwrap();
+        assert!(!plan.exact);
+        assert!(fs.render_cleanup(&planner).ends_with(&format!(
+            "(frees {} of {} needed, approximate)",
+            plan.freed, plan.needed
+        )));
+
+        let single = planner.smallest_directory(&fs).unwrap();
+        assert!(plan.needed <= plan.freed && plan.freed <= single.freed);
+        assert_eq!(
+            plan.directories
+                .iter()
+                .map(|&id| fs.total_size(id))
+                .sum::<u64>(),
+            plan.freed
+        );
+        let paths = paths(&fs, &plan);
+        for path in &paths {
+            let inside = format!("{}/", path);
+            assert!(paths.iter().all(|other| !other.starts_with(&inside)));
+        }
+    }
+
+    #[test]
+    fn test_cleanup_errors() {
+        let fs = example_filesystem();
+
+        assert_eq!(
+            CleanupPlanner {
+                capacity: 1000,
+                required_free: 10,
+            }
+            .smallest_directory(&fs),
+            Err(CleanupError::OverCapacity {
+                used: 48381165,
+                capacity: 1000
+            })
+        );
+        assert_eq!(
+            CleanupPlanner {
+                capacity: 50000000,
+                required_free: 60000000,
+            }
+            .smallest_set(&fs),
+            Err(CleanupError::Unsatisfiable {
+                required_free: 60000000,
+                capacity: 50000000
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_transcript() {
+        assert_eq!(
+            example_filesystem().to_transcript(),
+            "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+5626152 d.ext
+8033020 d.log
+4060174 j
+7214296 k
+$ cd .."
+        );
+    }
+
+    #[test]
+    fn test_empty_dirs_round_trip() {
+        let mut fs = Filesystem::new();
+        let a = fs.insert_dir(ROOT, "a").unwrap();
+        fs.insert_dir(a, "empty").unwrap();
+
+        assert_eq!(Filesystem::from(&fs.to_transcript()[..]), fs);
+        assert_eq!(
+            Filesystem::from(&Filesystem::new().to_transcript()[..]),
+            Filesystem::new()
+        );
+    }
+
+    #[test]
+    fn test_random_is_deterministic() {
+        let fs = Filesystem::random(42, 200, 1000);
+        assert_eq!(fs.walk().len(), 201);
+        assert_eq!(fs, Filesystem::random(42, 200, 1000));
+        assert_ne!(fs, Filesystem::random(43, 200, 1000));
+    }
+
+    #[test]
+    fn test_random_empty_files() {
+        let fs = Filesystem::random(3, 50, 0);
+        assert_eq!(fs.walk().len(), 51);
+        assert_eq!(fs.total_size(ROOT), 0);
+    }
+
+    #[test]
+    fn test_multi_gigabyte_tree() {
+        // files of up to 4 GiB each, so totals are far beyond what 32 bits can hold
+        let fs = Filesystem::random(11, 2000, 4 << 30);
+        let file_total = fs
+            .walk()
+            .into_iter()
+            .filter(|&id| !fs.is_dir(id))
+            .map(|id| fs.total_size(id))
+            .sum::<u64>();
+
+        assert!(fs.total_size(ROOT) > u32::MAX as u64 * 100);
+        assert_eq!(fs.total_size(ROOT), file_total);
+        assert_eq!(Filesystem::from(&fs.to_transcript()[..]), fs);
+
+        fs.directories().for_each(|dir| {
+            let children_total = fs.children(dir).map(|id| fs.total_size(id)).sum::<u64>();
+            assert_eq!(fs.total_size(dir), children_total);
+        });
+    }
+
+    #[test]
+    fn test_multi_gigabyte_transcript() {
+        let input = "$ cd /\n$ ls\ndir a\n5000000000 big.img\n$ cd a\n$ ls\n3000000000 b.iso";
+        let fs = Filesystem::from(input);
+
+        assert_eq!(fs.total_size(ROOT), 8000000000);
+        assert_eq!(fs.render(Report::Du), "7.5G\t/\n2.8G\t/a");
+        assert_eq!(
+            CleanupPlanner {
+                capacity: 10000000000,
+                required_free: 3000000000,
+            }
+            .smallest_directory(&fs)
+            .unwrap()
+            .freed,
+            3000000000
+        );
+    }
+
+    #[test]
+    fn test_size_overflow() {
+        let half = u64::MAX / 2 + 1;
+        let input = format!("$ ls\ndir a\n{} x\n$ cd a\n$ ls\n{} y", half, half);
+
+        assert_eq!(
+            Filesystem::from_transcript(&input),
+            Err(ShellError {
+                line: 6,
+                kind: ShellErrorKind::SizeOverflow(FsPath::from("/"))
+            })
+        );
+    }
+
+    /// A directory tree for proptest to generate and shrink, built into a `Filesystem` with `build`
+    #[derive(Debug, Clone)]
+    enum Entry {
+        File(u64),
+        Dir(BTreeMap<String, Entry>),
+    }
+
+    /// Names from a small alphabet, so the same name turns up in different directories
+    fn name() -> impl Strategy<Value = String> {
+        "[a-c]{1,2}(\\.[a-z]{1,3})?"
+    }
+
+    /// Directory contents, with empty files and empty directories among them
+    fn contents() -> impl Strategy<Value = BTreeMap<String, Entry>> {
+        let file = (0..1_u64 << 40).prop_map(Entry::File);
+        let entry = prop_oneof![Just(Entry::File(0)), file].prop_recursive(8, 64, 5, |inner| {
+            prop::collection::btree_map(name(), inner, 0..5).prop_map(Entry::Dir)
+        });
+        prop::collection::btree_map(name(), entry, 0..8)
+    }
+
+    /// Either a bushy tree, or a chain of directories far deeper than `contents` goes
+    fn tree() -> impl Strategy<Value = BTreeMap<String, Entry>> {
+        let chain =
+            (prop::collection::vec(name(), 1..100), contents()).prop_map(|(names, leaf)| {
+                names.into_iter().rev().fold(leaf, |inner, name| {
+                    BTreeMap::from([(name, Entry::Dir(inner))])
+                })
+            });
+        prop_oneof![contents(), chain]
+    }
+
+    fn build(fs: &mut Filesystem, dir: NodeId, contents: &BTreeMap<String, Entry>) {
+        for (name, entry) in contents {
+            match entry {
+                Entry::File(size) => {
+                    fs.insert_file(dir, name, *size).unwrap();
+                }
+                Entry::Dir(inner) => {
+                    let id = fs.insert_dir(dir, name).unwrap();
+                    build(fs, id, inner);
+                }
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_transcript_round_trip(tree in tree()) {
+            let mut fs = Filesystem::new();
+            build(&mut fs, ROOT, &tree);
+            let parsed = Filesystem::from(&fs.to_transcript()[..]);
+
+            prop_assert_eq!(parsed.total_size(ROOT), fs.total_size(ROOT));
+            prop_assert_eq!(parsed.walk().len(), fs.walk().len());
+            prop_assert!(parsed == fs);
+        }
+    }
+
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 7);
@@ -1,121 +1,891 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    ops::RangeInclusive,
+    str::FromStr,
+};
 
-#[derive(Debug)]
+use advent_of_code::helpers::{
+    grid::Grid,
+    ocr::recognize,
+    parsing::{ParseError, ParseErrorKind},
+};
+
+/// Kinds of operand an instruction can take
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OperandType {
+    Int,
+}
+
+/// How an instruction is written and how long it takes to execute
+#[derive(Debug, PartialEq)]
+struct InstructionSpec {
+    mnemonic: &'static str,
+    operands: &'static [OperandType],
+    cycles: u32,
+}
+
+/// Every instruction the CPU understands
+const INSTRUCTION_SET: [InstructionSpec; 2] = [
+    InstructionSpec {
+        mnemonic: "noop",
+        operands: &[],
+        cycles: 1,
+    },
+    InstructionSpec {
+        mnemonic: "addx",
+        operands: &[OperandType::Int],
+        cycles: 2,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Instruction {
     Noop,
     Addx(i32),
 }
 
+impl Instruction {
+    fn spec(&self) -> &'static InstructionSpec {
+        match self {
+            Instruction::Noop => &INSTRUCTION_SET[0],
+            Instruction::Addx(_) => &INSTRUCTION_SET[1],
+        }
+    }
+
+    /// Build an instruction from its spec and already-parsed operands
+    fn decode(spec: &InstructionSpec, operands: &[i32]) -> Instruction {
+        match (spec.mnemonic, operands) {
+            ("noop", []) => Instruction::Noop,
+            ("addx", [value]) => Instruction::Addx(*value),
+            _ => unreachable!("Operands are checked against the spec"),
+        }
+    }
+
+    /// Apply the instruction's effect, once all of its cycles have passed
+    fn execute(&self, x: &mut i32) {
+        match self {
+            Instruction::Noop => {}
+            Instruction::Addx(value) => *x += value,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.spec().mnemonic)?;
+        match self {
+            Instruction::Noop => Ok(()),
+            Instruction::Addx(value) => write!(f, " {}", value),
+        }
+    }
+}
+
+/// Split on whitespace, keeping each token's 1-based column
+fn tokens(line: &str) -> Vec<(usize, &str)> {
+    let mut column = 1;
+    let mut tokens = vec![];
+    for token in line.split(|c: char| c.is_ascii_whitespace()) {
+        if !token.is_empty() {
+            tokens.push((column, token));
+        }
+        column += token.len() + 1;
+    }
+    tokens
+}
+
+/// Parse a single instruction, looking its mnemonic up in the instruction set
+fn parse_instruction(line: &str) -> Result<Instruction, ParseError> {
+    let tokens = tokens(line);
+    let (column, mnemonic) = tokens.first().copied().unwrap_or((1, ""));
+    let spec = INSTRUCTION_SET
+        .iter()
+        .find(|spec| spec.mnemonic == mnemonic)
+        .ok_or_else(|| {
+            ParseError::new(column, ParseErrorKind::InvalidValue(mnemonic.to_string()))
+        })?;
+
+    let operands = tokens[1..]
+        .iter()
+        .map(|&(column, token)| {
+            token.parse::<i32>().map_err(|_| {
+                ParseError::new(column, ParseErrorKind::InvalidValue(token.to_string()))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if operands.len() != spec.operands.len() {
+        return Err(ParseError::new(
+            column,
+            ParseErrorKind::WrongCount {
+                expected: spec.operands.len(),
+                found: operands.len(),
+            },
+        ));
+    }
+
+    Ok(Instruction::decode(spec, &operands))
+}
+
+/// An assembled program, with the address each label points at
+#[derive(Debug, Default, PartialEq)]
+struct Program {
+    instructions: Vec<Instruction>,
+    labels: Vec<(String, usize)>,
+}
+
+impl Program {
+    /// Labels pointing at the instruction at `address`
+    fn labels_at(&self, address: usize) -> impl Iterator<Item = &str> {
+        self.labels
+            .iter()
+            .filter(move |&&(_, at)| at == address)
+            .map(|(label, _)| label.as_str())
+    }
+}
+
+impl From<Vec<Instruction>> for Program {
+    fn from(instructions: Vec<Instruction>) -> Program {
+        Program {
+            instructions,
+            labels: vec![],
+        }
+    }
+}
+
+/// Disassemble back to source, one instruction per line with labels on their own lines
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = vec![];
+        for address in 0..=self.instructions.len() {
+            lines.extend(self.labels_at(address).map(|label| format!("{}:", label)));
+            if let Some(instruction) = self.instructions.get(address) {
+                lines.push(instruction.to_string());
+            }
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Assemble source text, one instruction per line. Anything after `;` or `#` is a comment,
+/// and `name:` at the start of a line labels the next instruction.
+fn assemble(source: &str) -> Result<Program, ParseError> {
+    let mut program = Program::default();
+
+    for (index, line) in source.lines().enumerate() {
+        let located = |e: ParseError| e.on_line(index + 1);
+        let code = line.split([';', '#']).next().unwrap_or_default();
+        let mut rest = tokens(code);
+
+        while let Some(&(column, token)) = rest.first() {
+            let Some(label) = token.strip_suffix(':') else {
+                break;
+            };
+            let valid =
+                !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid || program.labels.iter().any(|(known, _)| known == label) {
+                return Err(located(ParseError::new(
+                    column,
+                    ParseErrorKind::InvalidValue(label.to_string()),
+                )));
+            }
+            program
+                .labels
+                .push((label.to_string(), program.instructions.len()));
+            rest.remove(0);
+        }
+
+        if let Some(&(column, _)) = rest.first() {
+            let instruction = parse_instruction(&code[column - 1..])
+                .map_err(|e| located(ParseError::new(e.column + column - 1, e.kind)))?;
+            program.instructions.push(instruction);
+        }
+    }
+
+    Ok(program)
+}
+
+fn parse_input(input: &str) -> Vec<Instruction> {
+    assemble(input)
+        .unwrap_or_else(|e| panic!("Invalid program: {}", e))
+        .instructions
+}
+
+/// Gets notified as the CPU works through its cycles
+trait CycleObserver {
+    /// Called in the middle of a cycle, before an instruction finishing this cycle takes effect
+    fn during_cycle(&mut self, _cpu: &Cpu) {}
+
+    /// Called at the end of a cycle, once any finished instruction has taken effect
+    fn after_cycle(&mut self, _cpu: &Cpu) {}
+}
+
+/// An instruction part way through execution
+#[derive(Debug, Clone, Copy)]
+struct Executing {
+    address: usize,
+    instruction: Instruction,
+    /// Cycles still needed, including the one in progress
+    remaining: u32,
+}
+
 struct Cpu {
     x: i32,
+    /// Number of the cycle in progress, or the last one completed between cycles
     cycle: u32,
-    current_instruction: Option<Instruction>,
-    cycles_remaining: u32,
+    program: Vec<Instruction>,
+    /// Index of the next instruction to fetch
+    pc: usize,
+    current: Option<Executing>,
 }
 
 impl Cpu {
-    fn new() -> Cpu {
+    fn new(program: Vec<Instruction>) -> Cpu {
         Cpu {
             x: 1,
             cycle: 0,
-            current_instruction: None,
-            cycles_remaining: 0,
+            program,
+            pc: 0,
+            current: None,
         }
     }
 
-    fn do_cycle(&mut self) {
-        self.cycle += 1;
-        self.cycles_remaining = (self.cycles_remaining as i32 - 1).max(0) as u32;
-        if self.cycles_remaining == 0 {
-            if let Some(Instruction::Addx(num)) = &self.current_instruction {
-                self.x += num;
-            }
-            self.current_instruction = None;
+    fn fetch(&mut self) -> Option<Executing> {
+        let instruction = *self.program.get(self.pc)?;
+        self.pc += 1;
+        Some(Executing {
+            address: self.pc - 1,
+            instruction,
+            remaining: instruction.spec().cycles,
+        })
+    }
+
+    /// Run a single cycle. Returns false once the program has finished.
+    fn step(&mut self, observer: &mut impl CycleObserver) -> bool {
+        if self.current.is_none() {
+            self.current = self.fetch();
+        }
+        let Some(executing) = self.current else {
+            return false;
         };
+
+        self.cycle += 1;
+        observer.during_cycle(self);
+
+        if executing.remaining == 1 {
+            executing.instruction.execute(&mut self.x);
+            self.current = None;
+        } else {
+            self.current = Some(Executing {
+                remaining: executing.remaining - 1,
+                ..executing
+            });
+        }
+
+        observer.after_cycle(self);
+        true
     }
 
-    fn set_instruction(&mut self, instruction: Instruction) {
-        match &instruction {
-            Instruction::Noop => self.cycles_remaining = 1,
-            Instruction::Addx(_) => self.cycles_remaining = 2,
+    /// Run the whole program
+    fn run(&mut self, observer: &mut impl CycleObserver) {
+        while self.step(observer) {}
+    }
+
+    /// Drive the program one cycle at a time, yielding `(cycle, x_during_cycle)`
+    fn cycles(&mut self) -> Cycles<'_> {
+        Cycles { cpu: self }
+    }
+}
+
+/// Remembers the cycle number and X register seen during the last cycle
+#[derive(Default)]
+struct During(Option<(u32, i32)>);
+
+impl CycleObserver for During {
+    fn during_cycle(&mut self, cpu: &Cpu) {
+        self.0 = Some((cpu.cycle, cpu.x));
+    }
+}
+
+struct Cycles<'a> {
+    cpu: &'a mut Cpu,
+}
+
+impl Iterator for Cycles<'_> {
+    type Item = (u32, i32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut during = During::default();
+        self.cpu.step(&mut during);
+        during.0
+    }
+}
+
+/// One line per cycle in a range, with the instruction executing and X before and after it
+struct Trace<'p> {
+    program: &'p Program,
+    cycles: RangeInclusive<u32>,
+    /// Address of the instruction executing and X, as seen during the cycle
+    during: (usize, i32),
+    lines: Vec<String>,
+}
+
+impl Trace<'_> {
+    fn new(program: &Program, cycles: RangeInclusive<u32>) -> Trace<'_> {
+        Trace {
+            program,
+            cycles,
+            during: (0, 0),
+            lines: vec![],
         }
+    }
+}
 
-        self.current_instruction = Some(instruction);
+impl CycleObserver for Trace<'_> {
+    fn during_cycle(&mut self, cpu: &Cpu) {
+        if let Some(executing) = cpu.current {
+            self.during = (executing.address, cpu.x);
+        }
+    }
+
+    fn after_cycle(&mut self, cpu: &Cpu) {
+        if !self.cycles.contains(&cpu.cycle) {
+            return;
+        }
+
+        let (address, x_before) = self.during;
+        let labels = self
+            .program
+            .labels_at(address)
+            .map(|label| format!("{}: ", label))
+            .collect::<String>();
+        let instruction = format!("{}{}", labels, self.program.instructions[address]);
+
+        self.lines.push(format!(
+            "{:>5}  {:<20} {:>4} -> {}",
+            cpu.cycle, instruction, x_before, cpu.x
+        ));
     }
 }
 
-fn parse_input(input: &str) -> Vec<Instruction> {
-    input
-        .lines()
-        .map(|line| {
-            if let Some((_, val)) = line.split_once("addx ") {
-                Instruction::Addx(val.parse().expect("Invalid integer"))
-            } else {
-                Instruction::Noop
-            }
-        })
-        .collect()
+/// The 20th, 60th, 100th, 140th, 180th and 220th cycles
+fn is_checkpoint(cycle: u32) -> bool {
+    cycle <= 220 && (cycle + 20).is_multiple_of(40)
 }
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let mut instructions = parse_input(input);
-    instructions.reverse();
+/// Screen size and sprite width used by the puzzle
+const CRT_WIDTH: usize = 40;
+const CRT_HEIGHT: usize = 6;
+const SPRITE_WIDTH: usize = 3;
 
-    let mut cpu = Cpu::new();
-    let mut signals: HashMap<u32, i32> = HashMap::new();
+/// Draws one pixel per cycle, left to right and top to bottom. A pixel is lit when the
+/// sprite, centered on X, covers its column.
+struct Crt {
+    pixels: Grid<bool>,
+    sprite_width: usize,
+}
 
-    while instructions.is_empty() {
-        cpu.do_cycle();
-        if matches!(cpu.current_instruction, None) {
-            cpu.set_instruction(instructions.pop().unwrap())
-        };
+impl Crt {
+    fn new(width: usize, height: usize, sprite_width: usize) -> Crt {
+        Crt {
+            pixels: Grid::filled(width, height, false),
+            sprite_width,
+        }
+    }
 
-        if (cpu.cycle as i32 - 20) % 40 == 0 {
-            signals.insert(cpu.cycle, cpu.x);
+    /// Draw the pixel for `cycle`, given the sprite position during that cycle
+    fn draw(&mut self, cycle: u32, x: i32) {
+        let position = (cycle - 1) as usize;
+        let width = self.pixels.width();
+        let (column, row) = (position % width, position / width);
+
+        let left = x as i64 - (self.sprite_width as i64 - 1) / 2;
+        let covered = (left..left + self.sprite_width as i64).contains(&(column as i64));
+        if let Some(pixel) = self.pixels.get_mut((column, row)) {
+            *pixel = covered;
         }
     }
 
-    Some(
-        signals
-            .iter()
-            .map(|(&cycle, &value)| cycle as i32 * value)
-            .sum(),
-    )
+    /// Draw every cycle the driver yields
+    fn scan(&mut self, cycles: impl Iterator<Item = (u32, i32)>) {
+        for (cycle, x) in cycles {
+            self.draw(cycle, x);
+        }
+    }
+
+    /// One line of text per row, using the given characters for lit and dark pixels
+    fn render(&self, lit: char, dark: char) -> String {
+        self.pixels
+            .map(|&on| if on { lit } else { dark })
+            .to_string()
+    }
+
+    /// The screen as a plain (P1) portable bitmap, where 1 is a black pixel
+    fn to_pbm(&self) -> String {
+        format!(
+            "P1\n{} {}\n{}\n",
+            self.pixels.width(),
+            self.pixels.height(),
+            self.render('1', '0')
+        )
+    }
+}
+
+impl Default for Crt {
+    fn default() -> Crt {
+        Crt::new(CRT_WIDTH, CRT_HEIGHT, SPRITE_WIDTH)
+    }
+}
+
+impl CycleObserver for Crt {
+    fn during_cycle(&mut self, cpu: &Cpu) {
+        self.draw(cpu.cycle, cpu.x);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn holds(self, left: i32, right: i32) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Comparison, String> {
+        [
+            Comparison::Eq,
+            Comparison::Ne,
+            Comparison::Lt,
+            Comparison::Le,
+            Comparison::Gt,
+            Comparison::Ge,
+        ]
+        .into_iter()
+        .find(|comparison| comparison.symbol() == s)
+        .ok_or_else(|| format!("unknown comparison \"{}\"", s))
+    }
+}
+
+/// Pauses the debugger when X changes to meet a condition
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Breakpoint {
+    comparison: Comparison,
+    value: i32,
+}
+
+impl Breakpoint {
+    fn holds(&self, x: i32) -> bool {
+        self.comparison.holds(x, self.value)
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "x {} {}", self.comparison.symbol(), self.value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    /// Run this many cycles
+    Step(u32),
+    /// Run until the given cycle is about to start
+    RunTo(u32),
+    Continue,
+    /// Add a breakpoint, or list them without one
+    Break(Option<Breakpoint>),
+    Delete(usize),
+    Registers,
+    Crt,
+    Help,
+    Quit,
+}
+
+const DEBUGGER_HELP: &str = "\
+step [n]           run one cycle, or n cycles
+run <cycle>        run until the given cycle is about to start
+continue           run until a breakpoint or the end of the program
+break x <op> <n>   pause when X changes to meet the condition; op is one of == != < <= > >=
+break              list breakpoints
+delete <i>         remove breakpoint i
+regs               print the registers
+crt                print the CRT frame drawn so far
+quit               leave the debugger";
+
+fn parse_number<T: FromStr>(token: &str) -> Result<T, String> {
+    token
+        .parse()
+        .map_err(|_| format!("expected a number, found \"{}\"", token))
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let tokens = line.split_whitespace().collect::<Vec<_>>();
+    match tokens[..] {
+        ["step" | "s"] => Ok(Command::Step(1)),
+        ["step" | "s", cycles] => Ok(Command::Step(parse_number(cycles)?)),
+        ["run" | "r", cycle] => Ok(Command::RunTo(parse_number(cycle)?)),
+        ["continue" | "c"] => Ok(Command::Continue),
+        ["break" | "b"] => Ok(Command::Break(None)),
+        ["break" | "b", "x", comparison, value] => Ok(Command::Break(Some(Breakpoint {
+            comparison: comparison.parse()?,
+            value: parse_number(value)?,
+        }))),
+        ["delete" | "d", index] => Ok(Command::Delete(parse_number(index)?)),
+        ["regs" | "registers"] => Ok(Command::Registers),
+        ["crt"] => Ok(Command::Crt),
+        ["help" | "h"] => Ok(Command::Help),
+        ["quit" | "q"] => Ok(Command::Quit),
+        _ => Err(format!("unknown command \"{}\", try help", line.trim())),
+    }
+}
+
+/// Why the debugger stopped running
+enum Stop {
+    Done,
+    Breakpoint(usize),
+    Halted,
+}
+
+/// Steps a CPU by hand while drawing its CRT frame
+struct Debugger {
+    cpu: Cpu,
+    crt: Crt,
+    breakpoints: Vec<Breakpoint>,
 }
 
-pub fn part_two(input: &str) -> Option<i32> {
-    let mut instructions = parse_input(input);
-    instructions.reverse();
+impl Debugger {
+    fn new(program: Vec<Instruction>) -> Debugger {
+        Debugger {
+            cpu: Cpu::new(program),
+            crt: Crt::default(),
+            breakpoints: vec![],
+        }
+    }
+
+    /// Run cycles while `more` holds, stopping early at a breakpoint or the end of the program
+    fn run_while(&mut self, more: impl Fn(&Cpu) -> bool) -> Stop {
+        while more(&self.cpu) {
+            let before = self.cpu.x;
+            if !self.cpu.step(&mut self.crt) {
+                return Stop::Halted;
+            }
+            let hit = self
+                .breakpoints
+                .iter()
+                .position(|b| b.holds(self.cpu.x) && !b.holds(before));
+            if let Some(index) = hit {
+                return Stop::Breakpoint(index);
+            }
+        }
+        Stop::Done
+    }
+
+    /// Where the CPU is paused: X as seen during the next cycle, and what that cycle executes
+    fn status(&self) -> String {
+        let (instruction, cycle_of) = match (self.cpu.current, self.cpu.program.get(self.cpu.pc)) {
+            (Some(executing), _) => (
+                executing.instruction,
+                executing.instruction.spec().cycles - executing.remaining + 1,
+            ),
+            (None, Some(&instruction)) => (instruction, 1),
+            (None, None) => {
+                return format!("halted after cycle {}: x = {}", self.cpu.cycle, self.cpu.x)
+            }
+        };
+
+        format!(
+            "cycle {}: x = {}, executing {} (cycle {} of {})",
+            self.cpu.cycle + 1,
+            self.cpu.x,
+            instruction,
+            cycle_of,
+            instruction.spec().cycles
+        )
+    }
+
+    fn registers(&self) -> String {
+        format!(
+            "x = {}\npc = {}\ncycles completed = {}",
+            self.cpu.x, self.cpu.pc, self.cpu.cycle
+        )
+    }
+
+    /// The rows drawn so far, leaving pixels that haven't been drawn blank
+    fn frame(&self) -> String {
+        let drawn = self.cpu.cycle as usize;
+        let width = self.crt.pixels.width();
 
-    let mut cpu = Cpu::new();
-    let mut drawn_pixels: HashSet<u32> = HashSet::new();
+        self.crt
+            .pixels
+            .rows()
+            .enumerate()
+            .take(drawn.div_ceil(width.max(1)))
+            .map(|(y, row)| {
+                row.iter()
+                    .take(drawn - y * width)
+                    .map(|&lit| if lit { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
-    while !instructions.is_empty() {
-        println!("{:?} - {:?}", cpu.cycle, cpu.x);
-        if ((cpu.cycle as i32 % 40).abs_diff(cpu.x)) <= 1 {
-            drawn_pixels.insert(cpu.cycle);
+    fn stopped(&self, stop: Stop) -> String {
+        match stop {
+            Stop::Done | Stop::Halted => self.status(),
+            Stop::Breakpoint(index) => format!(
+                "breakpoint {} ({}) hit\n{}",
+                index,
+                self.breakpoints[index],
+                self.status()
+            ),
         }
-        if matches!(cpu.current_instruction, None) {
-            cpu.set_instruction(instructions.pop().unwrap())
+    }
+
+    /// Carry out a command, returning what to print, or `None` to quit
+    fn execute(&mut self, command: Command) -> Option<String> {
+        let output = match command {
+            Command::Step(cycles) => {
+                let target = self.cpu.cycle.saturating_add(cycles);
+                let stop = self.run_while(|cpu| cpu.cycle < target);
+                self.stopped(stop)
+            }
+            Command::RunTo(cycle) if cycle <= self.cpu.cycle => {
+                format!("already past cycle {}\n{}", cycle, self.status())
+            }
+            Command::RunTo(cycle) => {
+                let stop = self.run_while(|cpu| cpu.cycle + 1 < cycle);
+                self.stopped(stop)
+            }
+            Command::Continue => {
+                let stop = self.run_while(|_| true);
+                self.stopped(stop)
+            }
+            Command::Break(Some(breakpoint)) => {
+                self.breakpoints.push(breakpoint);
+                format!(
+                    "breakpoint {} ({}) set",
+                    self.breakpoints.len() - 1,
+                    breakpoint
+                )
+            }
+            Command::Break(None) if self.breakpoints.is_empty() => "no breakpoints".to_string(),
+            Command::Break(None) => self
+                .breakpoints
+                .iter()
+                .enumerate()
+                .map(|(index, breakpoint)| format!("{}: {}", index, breakpoint))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Command::Delete(index) if index < self.breakpoints.len() => {
+                format!(
+                    "breakpoint {} ({}) deleted",
+                    index,
+                    self.breakpoints.remove(index)
+                )
+            }
+            Command::Delete(index) => format!("no breakpoint {}", index),
+            Command::Registers => self.registers(),
+            Command::Crt => self.frame(),
+            Command::Help => DEBUGGER_HELP.to_string(),
+            Command::Quit => return None,
         };
-        cpu.do_cycle();
+        Some(output)
     }
 
-    println!("{:?}", drawn_pixels);
+    /// Read commands line by line until `quit` or the end of the input
+    fn repl(
+        &mut self,
+        input: impl BufRead,
+        output: &mut impl Write,
+        prompt: bool,
+    ) -> io::Result<()> {
+        writeln!(output, "{}", self.status())?;
+        let mut lines = input.lines();
 
-    let monitor = (0..6).map(|row| {
-        (row * 40..(row + 1) * 40)
-            .map(|px| if drawn_pixels.contains(&px) { '#' } else { '.' })
-            .collect::<String>()
-    });
+        loop {
+            if prompt {
+                write!(output, "(cpu) ")?;
+                output.flush()?;
+            }
+            let Some(line) = lines.next().transpose()? else {
+                return Ok(());
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
 
-    monitor.for_each(|row| println!("{}", row));
+            let response = match parse_command(&line) {
+                Ok(command) => match self.execute(command) {
+                    Some(response) => response,
+                    None => return Ok(()),
+                },
+                Err(e) => e,
+            };
+            writeln!(output, "{}", response)?;
+        }
+    }
+}
+
+pub fn part_one(input: &str) -> Option<i32> {
+    let total = Cpu::new(parse_input(input))
+        .cycles()
+        .filter(|&(cycle, _)| is_checkpoint(cycle))
+        .map(|(cycle, x)| cycle as i32 * x)
+        .sum();
 
-    None
+    Some(total)
+}
+
+pub fn part_two(input: &str) -> Option<String> {
+    let mut crt = Crt::default();
+    crt.scan(Cpu::new(parse_input(input)).cycles());
+
+    recognize(&crt.pixels)
+        .map_err(|e| eprintln!("Couldn't read the CRT: {}", e))
+        .ok()
+}
+
+struct Args {
+    width: usize,
+    height: usize,
+    sprite_width: usize,
+    /// Characters to draw lit and dark pixels with, when printing the screen
+    display: Option<(char, char)>,
+    /// Where to write the screen as a portable bitmap
+    pbm: Option<String>,
+    /// Cycles to print a trace for
+    trace: Option<RangeInclusive<u32>>,
+    disassemble: bool,
+    debug: bool,
+}
+
+/// Parse a screen or sprite size, which can't be zero
+fn parse_size(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(size) => Ok(size),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    let show = args.contains("--display");
+    let lit = args.opt_value_from_str("--lit")?;
+    let dark = args.opt_value_from_str("--dark")?;
+    let trace = args.contains("--trace");
+    let from = args.opt_value_from_str("--from")?.unwrap_or(1);
+    let to = args.opt_value_from_str("--to")?.unwrap_or(u32::MAX);
+
+    Ok(Args {
+        width: args
+            .opt_value_from_fn("--width", parse_size)?
+            .unwrap_or(CRT_WIDTH),
+        height: args
+            .opt_value_from_fn("--height", parse_size)?
+            .unwrap_or(CRT_HEIGHT),
+        sprite_width: args
+            .opt_value_from_fn("--sprite", parse_size)?
+            .unwrap_or(SPRITE_WIDTH),
+        display: (show || lit.is_some() || dark.is_some())
+            .then(|| (lit.unwrap_or('#'), dark.unwrap_or('.'))),
+        pbm: args.opt_value_from_str("--pbm")?,
+        trace: trace.then_some(from..=to),
+        disassemble: args.contains("--disassemble"),
+        debug: args.contains("--debug"),
+    })
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 10);
+
+    if args.debug {
+        let mut debugger = Debugger::new(parse_input(input));
+        let stdin = io::stdin();
+        let prompt = io::IsTerminal::is_terminal(&stdin);
+        if let Err(e) = debugger.repl(stdin.lock(), &mut io::stdout(), prompt) {
+            eprintln!("Debugger failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.disassemble || args.trace.is_some() {
+        let program = assemble(input).unwrap_or_else(|e| {
+            eprintln!("Invalid program: {}", e);
+            std::process::exit(1);
+        });
+
+        if args.disassemble {
+            println!("{}", program);
+        }
+        if let Some(cycles) = args.trace {
+            let mut trace = Trace::new(&program, cycles);
+            Cpu::new(program.instructions.clone()).run(&mut trace);
+            println!(
+                "{:>5}  {:<20} {:>4} -> x after",
+                "cycle", "instruction", "x"
+            );
+            println!("{}", trace.lines.join("\n"));
+        }
+        return;
+    }
+
+    if args.display.is_some() || args.pbm.is_some() {
+        let mut crt = Crt::new(args.width, args.height, args.sprite_width);
+        crt.scan(Cpu::new(parse_input(input)).cycles());
+
+        if let Some((lit, dark)) = args.display {
+            println!("{}", crt.render(lit, dark));
+        }
+        if let Some(path) = args.pbm {
+            if let Err(e) = std::fs::write(&path, crt.to_pbm()) {
+                eprintln!("Failed to write {}: {}", path, e);
+                std::process::exit(1);
+            }
+            println!("Wrote the screen to {}", path);
+        }
+        return;
+    }
+
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -123,6 +893,339 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
+
+    /// Records X during and after every cycle
+    #[derive(Default)]
+    struct Recorder {
+        during: Vec<(u32, i32)>,
+        after: Vec<(u32, i32)>,
+    }
+
+    impl CycleObserver for Recorder {
+        fn during_cycle(&mut self, cpu: &Cpu) {
+            self.during.push((cpu.cycle, cpu.x));
+        }
+
+        fn after_cycle(&mut self, cpu: &Cpu) {
+            self.after.push((cpu.cycle, cpu.x));
+        }
+    }
+
+    #[rstest]
+    #[case("noop", Instruction::Noop)]
+    #[case("addx 15", Instruction::Addx(15))]
+    #[case("addx -11", Instruction::Addx(-11))]
+    fn test_parse_instruction(#[case] line: &str, #[case] expected: Instruction) {
+        assert_eq!(parse_instruction(line), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("mulx 3", ParseError::new(1, ParseErrorKind::InvalidValue("mulx".to_string())))]
+    #[case("addx three", ParseError::new(6, ParseErrorKind::InvalidValue("three".to_string())))]
+    #[case("addx", ParseError::new(1, ParseErrorKind::WrongCount { expected: 1, found: 0 }))]
+    #[case("noop 1", ParseError::new(1, ParseErrorKind::WrongCount { expected: 0, found: 1 }))]
+    fn test_parse_instruction_errors(#[case] line: &str, #[case] expected: ParseError) {
+        assert_eq!(parse_instruction(line), Err(expected));
+    }
+
+    #[test]
+    fn test_assemble_comments_and_labels() {
+        let source = "\
+start: addx 3   ; bump X
+# a whole-line comment
+
+  noop
+twice: again: addx -5
+end:";
+        let program = assemble(source).unwrap();
+
+        assert_eq!(
+            program.instructions,
+            vec![
+                Instruction::Addx(3),
+                Instruction::Noop,
+                Instruction::Addx(-5)
+            ]
+        );
+        assert_eq!(
+            program.labels,
+            vec![
+                ("start".to_string(), 0),
+                ("twice".to_string(), 2),
+                ("again".to_string(), 2),
+                ("end".to_string(), 3),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("noop\n  mulx 3", ParseError::new(3, ParseErrorKind::InvalidValue("mulx".to_string())).on_line(2))]
+    #[case("go: addx x", ParseError::new(10, ParseErrorKind::InvalidValue("x".to_string())))]
+    #[case("a:\na: noop", ParseError::new(1, ParseErrorKind::InvalidValue("a".to_string())).on_line(2))]
+    #[case("bad-label: noop", ParseError::new(1, ParseErrorKind::InvalidValue("bad-label".to_string())))]
+    #[case("noop ; fine\naddx", ParseError::new(1, ParseErrorKind::WrongCount { expected: 1, found: 0 }).on_line(2))]
+    fn test_assemble_errors(#[case] source: &str, #[case] expected: ParseError) {
+        assert_eq!(assemble(source), Err(expected));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let program = assemble("start: addx 3 ; comment\nnoop\nend:").unwrap();
+        assert_eq!(program.to_string(), "start:\naddx 3\nnoop\nend:");
+        assert_eq!(assemble(&program.to_string()), Ok(program));
+
+        let example = advent_of_code::read_file("examples", 10);
+        let program = Program::from(parse_input(&example));
+        assert_eq!(program.to_string(), example.trim_end());
+    }
+
+    #[test]
+    fn test_trace() {
+        let program = assemble("noop\nloop: addx 3\naddx -5").unwrap();
+        let mut trace = Trace::new(&program, 2..=4);
+        Cpu::new(program.instructions.clone()).run(&mut trace);
+
+        assert_eq!(
+            trace.lines,
+            vec![
+                "    2  loop: addx 3            1 -> 1",
+                "    3  loop: addx 3            1 -> 4",
+                "    4  addx -5                 4 -> 4",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("step", Ok(Command::Step(1)))]
+    #[case("s 20", Ok(Command::Step(20)))]
+    #[case("run 220", Ok(Command::RunTo(220)))]
+    #[case("  c ", Ok(Command::Continue))]
+    #[case("break x >= -3", Ok(Command::Break(Some(Breakpoint { comparison: Comparison::Ge, value: -3 }))))]
+    #[case("break", Ok(Command::Break(None)))]
+    #[case("delete 1", Ok(Command::Delete(1)))]
+    #[case("step two", Err("expected a number, found \"two\"".to_string()))]
+    #[case("break x => 3", Err("unknown comparison \"=>\"".to_string()))]
+    #[case("jump 3", Err("unknown command \"jump 3\", try help".to_string()))]
+    fn test_parse_command(#[case] line: &str, #[case] expected: Result<Command, String>) {
+        assert_eq!(parse_command(line), expected);
+    }
+
+    fn debug(program: &str, script: &str) -> String {
+        let mut output = vec![];
+        Debugger::new(parse_input(program))
+            .repl(script.as_bytes(), &mut output, false)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_debugger_stepping() {
+        let output = debug(
+            "noop\naddx 3\naddx -5",
+            "step\nstep\nregs\nstep 2\nstep 5\nstep\n",
+        );
+
+        assert_eq!(
+            output,
+            "\
+cycle 1: x = 1, executing noop (cycle 1 of 1)
+cycle 2: x = 1, executing addx 3 (cycle 1 of 2)
+cycle 3: x = 1, executing addx 3 (cycle 2 of 2)
+x = 1
+pc = 2
+cycles completed = 2
+cycle 5: x = 4, executing addx -5 (cycle 2 of 2)
+halted after cycle 5: x = -1
+halted after cycle 5: x = -1
+"
+        );
+    }
+
+    #[test]
+    fn test_debugger_run_to_checkpoint() {
+        let example = advent_of_code::read_file("examples", 10);
+        let output = debug(&example, "run 20\nrun 10\nrun 60\nq\nrun 100\n");
+
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                "cycle 1: x = 1, executing addx 15 (cycle 1 of 2)",
+                "cycle 20: x = 21, executing addx -1 (cycle 1 of 2)",
+                "already past cycle 10",
+                "cycle 20: x = 21, executing addx -1 (cycle 1 of 2)",
+                "cycle 60: x = 19, executing addx -3 (cycle 1 of 2)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_debugger_breakpoints() {
+        let output = debug(
+            "noop\naddx 3\naddx -5\naddx 6",
+            "break x < 0\nbreak x == 4\nbreak\ncontinue\ncontinue\ndelete 0\ndelete 0\nc\n",
+        );
+
+        assert_eq!(
+            output,
+            "\
+cycle 1: x = 1, executing noop (cycle 1 of 1)
+breakpoint 0 (x < 0) set
+breakpoint 1 (x == 4) set
+0: x < 0
+1: x == 4
+breakpoint 1 (x == 4) hit
+cycle 4: x = 4, executing addx -5 (cycle 1 of 2)
+breakpoint 0 (x < 0) hit
+cycle 6: x = -1, executing addx 6 (cycle 1 of 2)
+breakpoint 0 (x < 0) deleted
+breakpoint 0 (x == 4) deleted
+halted after cycle 7: x = 5
+"
+        );
+    }
+
+    #[test]
+    fn test_debugger_partial_frame() {
+        let example = advent_of_code::read_file("examples", 10);
+        let output = debug(&example, "step 44\ncrt\n");
+
+        assert_eq!(
+            output.lines().skip(2).collect::<Vec<_>>(),
+            vec!["##..##..##..##..##..##..##..##..##..##..", "###."]
+        );
+    }
+
+    #[test]
+    fn test_instruction_table() {
+        assert_eq!(Instruction::Noop.spec().cycles, 1);
+        assert_eq!(Instruction::Addx(3).spec().mnemonic, "addx");
+        assert_eq!(Instruction::Addx(3).spec().cycles, 2);
+    }
+
+    #[test]
+    fn test_small_program() {
+        let mut recorder = Recorder::default();
+        let mut cpu = Cpu::new(parse_input("noop\naddx 3\naddx -5"));
+        while cpu.step(&mut recorder) {}
+
+        assert_eq!(
+            recorder.during,
+            vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]
+        );
+        assert_eq!(
+            recorder.after,
+            vec![(1, 1), (2, 1), (3, 4), (4, 4), (5, -1)]
+        );
+        assert_eq!(cpu.x, -1);
+        assert!(!cpu.step(&mut recorder));
+    }
+
+    #[test]
+    fn test_cycles_match_observer() {
+        let mut recorder = Recorder::default();
+        let mut cpu = Cpu::new(parse_input("noop\naddx 3\naddx -5"));
+        while cpu.step(&mut recorder) {}
+        let cycles = Cpu::new(parse_input("noop\naddx 3\naddx -5"))
+            .cycles()
+            .collect::<Vec<_>>();
+
+        assert_eq!(cycles, recorder.during);
+    }
+
+    #[test]
+    fn test_example_runs_a_full_frame() {
+        let input = advent_of_code::read_file("examples", 10);
+        assert_eq!(Cpu::new(parse_input(&input)).cycles().count(), 240);
+    }
+
+    #[rstest]
+    #[case(20, 21, 420)]
+    #[case(60, 19, 1140)]
+    #[case(100, 18, 1800)]
+    #[case(140, 21, 2940)]
+    #[case(180, 16, 2880)]
+    #[case(220, 18, 3960)]
+    fn test_example_checkpoints(#[case] cycle: u32, #[case] x: i32, #[case] strength: i32) {
+        let input = advent_of_code::read_file("examples", 10);
+        let during = Cpu::new(parse_input(&input))
+            .cycles()
+            .find(|&(n, _)| n == cycle);
+
+        assert!(is_checkpoint(cycle));
+        assert_eq!(during, Some((cycle, x)));
+        assert_eq!(cycle as i32 * x, strength);
+    }
+
+    #[rstest]
+    #[case(19)]
+    #[case(21)]
+    #[case(40)]
+    #[case(260)]
+    fn test_not_checkpoints(#[case] cycle: u32) {
+        assert!(!is_checkpoint(cycle));
+    }
+
+    const EXAMPLE_SCREEN: &str = "\
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....";
+
+    fn example_crt(width: usize, height: usize, sprite_width: usize) -> Crt {
+        let input = advent_of_code::read_file("examples", 10);
+        let mut crt = Crt::new(width, height, sprite_width);
+        crt.scan(Cpu::new(parse_input(&input)).cycles());
+        crt
+    }
+
+    #[test]
+    fn test_render_example() {
+        assert_eq!(example_crt(40, 6, 3).render('#', '.'), EXAMPLE_SCREEN);
+        assert_eq!(
+            example_crt(40, 6, 3).render('█', ' '),
+            EXAMPLE_SCREEN.replace('#', "█").replace('.', " ")
+        );
+    }
+
+    #[test]
+    fn test_configurable_size() {
+        let crt = example_crt(20, 3, 3);
+        assert_eq!(crt.render('#', '.').lines().count(), 3);
+        assert_eq!(
+            crt.render('#', '.').lines().next(),
+            Some("##..##..##..##..##..")
+        );
+
+        // A wider sprite lights every pixel the narrow one does, and then some
+        let narrow = example_crt(40, 6, 3);
+        let wide = example_crt(40, 6, 5);
+        let lit = |crt: &Crt| crt.pixels.positions().filter(|&p| crt.pixels[p]).count();
+        assert!(narrow
+            .pixels
+            .positions()
+            .all(|p| !narrow.pixels[p] || wide.pixels[p]));
+        assert!(lit(&wide) > lit(&narrow));
+    }
+
+    #[test]
+    fn test_pbm() {
+        let mut crt = Crt::new(4, 2, 1);
+        crt.scan([(1, 0), (2, 0), (3, 2), (4, 0), (5, 3), (6, 1)].into_iter());
+
+        assert_eq!(crt.to_pbm(), "P1\n4 2\n1010\n0100\n");
+    }
+
+    #[rstest]
+    #[case("40", Ok(40))]
+    #[case("1", Ok(1))]
+    #[case("0", Err("must be at least 1".to_string()))]
+    #[case("-3", Err("invalid digit found in string".to_string()))]
+    fn test_parse_size(#[case] value: &str, #[case] expected: Result<usize, String>) {
+        assert_eq!(parse_size(value), expected);
+    }
 
     #[test]
     fn test_part_one() {
@@ -0,0 +1,96 @@
+/*
+ * This file contains template code.
+ * There is no need to edit this file unless you want to change template functionality.
+ */
+use advent_of_code::template::{download, example::extract_example, scaffold};
+use std::{fs, path::Path, process};
+
+struct Args {
+    day: u8,
+    year: Option<i16>,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        day: args.free_from_str()?,
+        year: args.opt_value_from_str(["-y", "--year"])?,
+    })
+}
+
+/// Write an extracted example, keeping any example file that already has contents
+fn write_example(day: u8, puzzle: Option<&String>) -> Result<String, String> {
+    let path = scaffold::example_path(day);
+
+    if scaffold::has_contents(Path::new(&path)) {
+        return Ok(format!("example \"{}\" (already filled in)", path));
+    }
+
+    let puzzle = puzzle.ok_or(format!(
+        "example \"{}\" (no puzzle description downloaded)",
+        path
+    ))?;
+    let example = extract_example(puzzle).ok_or(format!(
+        "example \"{}\" (no example found in puzzle description)",
+        path
+    ))?;
+
+    fs::write(&path, example)
+        .map(|_| format!("example \"{}\"", path))
+        .map_err(|e| format!("example \"{}\" ({})", path, e))
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(_) => {
+            eprintln!("Need to specify a day (as integer). example: `cargo new-day 7`");
+            process::exit(1);
+        }
+    };
+
+    // scaffolding is the only required step, everything after it is best effort.
+    if let Err(e) = scaffold::scaffold(args.day, false, false) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+
+    // an input that's already there is never fetched again, so it can't be overwritten.
+    let input_path = scaffold::input_path(args.day);
+    let download = if scaffold::has_contents(Path::new(&input_path)) {
+        None
+    } else {
+        Some(download::download(args.day, args.year, true))
+    };
+    if let Some(Err(e)) = &download {
+        eprintln!("Skipping download: {}", e);
+    }
+
+    let puzzle = download
+        .as_ref()
+        .and_then(|d| d.as_ref().ok())
+        .and_then(|d| d.puzzle.as_ref());
+
+    let checklist = [
+        Ok(format!("module \"{}\"", scaffold::module_path(args.day))),
+        match &download {
+            None => Ok(format!("input \"{}\" (already present)", input_path)),
+            Some(download) => download
+                .as_ref()
+                .map(|d| format!("input \"{}\"", d.input_path))
+                .map_err(|e| format!("input \"{}\" ({})", input_path, e)),
+        },
+        write_example(args.day, puzzle),
+    ];
+
+    println!("---");
+    println!("🎄 Day {:02} checklist:", args.day);
+    checklist.iter().for_each(|item| match item {
+        Ok(done) => println!("  [x] {}", done),
+        Err(missing) => println!("  [ ] {}", missing),
+    });
+    println!(
+        "🎄 Type `cargo solve {:02}` to run your solution.",
+        args.day
+    );
+}
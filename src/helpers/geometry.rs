@@ -0,0 +1,390 @@
+use std::{
+    convert::TryFrom,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use super::parsing::{ParseError, ParseErrorKind};
+
+/// A point (or displacement) on an integer plane, with y growing downwards like a grid
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// Displacements share their representation with positions
+pub type Vec2 = Point;
+
+impl Point {
+    pub const ORIGIN: Point = Point { x: 0, y: 0 };
+
+    pub const fn new(x: i64, y: i64) -> Point {
+        Point { x, y }
+    }
+
+    /// Distance moving only orthogonally
+    pub fn manhattan(&self, other: Point) -> u64 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// Distance moving orthogonally or diagonally, i.e. king moves
+    pub fn chebyshev(&self, other: Point) -> u64 {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+
+    /// Clamp each component to -1, 0 or 1, giving a single step towards this vector
+    pub fn signum(&self) -> Point {
+        Point::new(self.x.signum(), self.y.signum())
+    }
+
+    /// Rotate a quarter turn clockwise around the origin (north becomes east)
+    pub fn rotate_cw(&self) -> Point {
+        Point::new(-self.y, self.x)
+    }
+
+    /// Rotate a quarter turn counter-clockwise around the origin (north becomes west)
+    pub fn rotate_ccw(&self) -> Point {
+        Point::new(self.y, -self.x)
+    }
+
+    /// Grid location of this point, if both components are non-negative
+    pub fn to_grid(&self) -> Option<(usize, usize)> {
+        Some((usize::try_from(self.x).ok()?, usize::try_from(self.y).ok()?))
+    }
+}
+
+impl From<(i64, i64)> for Point {
+    fn from((x, y): (i64, i64)) -> Self {
+        Point::new(x, y)
+    }
+}
+
+impl From<(usize, usize)> for Point {
+    fn from((x, y): (usize, usize)) -> Self {
+        Point::new(x as i64, y as i64)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Self::Output {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Self::Output {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Self::Output {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i64> for Point {
+    type Output = Point;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        Point::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Point) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, rhs: Point) {
+        *self = *self - rhs;
+    }
+}
+
+/// One of the four cardinal directions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dir4 {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Dir4 {
+    /// All directions, clockwise from north
+    pub const ALL: [Dir4; 4] = [Dir4::North, Dir4::East, Dir4::South, Dir4::West];
+
+    pub fn offset(&self) -> Vec2 {
+        match self {
+            Dir4::North => Point::new(0, -1),
+            Dir4::East => Point::new(1, 0),
+            Dir4::South => Point::new(0, 1),
+            Dir4::West => Point::new(-1, 0),
+        }
+    }
+
+    pub fn rotate_cw(&self) -> Dir4 {
+        Dir4::ALL[(*self as usize + 1) % 4]
+    }
+
+    pub fn rotate_ccw(&self) -> Dir4 {
+        Dir4::ALL[(*self as usize + 3) % 4]
+    }
+
+    pub fn opposite(&self) -> Dir4 {
+        Dir4::ALL[(*self as usize + 2) % 4]
+    }
+}
+
+/// One of the four cardinal or four diagonal directions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dir8 {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Dir8 {
+    /// All directions, clockwise from north
+    pub const ALL: [Dir8; 8] = [
+        Dir8::North,
+        Dir8::NorthEast,
+        Dir8::East,
+        Dir8::SouthEast,
+        Dir8::South,
+        Dir8::SouthWest,
+        Dir8::West,
+        Dir8::NorthWest,
+    ];
+
+    pub fn offset(&self) -> Vec2 {
+        match self {
+            Dir8::North => Point::new(0, -1),
+            Dir8::NorthEast => Point::new(1, -1),
+            Dir8::East => Point::new(1, 0),
+            Dir8::SouthEast => Point::new(1, 1),
+            Dir8::South => Point::new(0, 1),
+            Dir8::SouthWest => Point::new(-1, 1),
+            Dir8::West => Point::new(-1, 0),
+            Dir8::NorthWest => Point::new(-1, -1),
+        }
+    }
+
+    /// Rotate an eighth turn clockwise
+    pub fn rotate_cw(&self) -> Dir8 {
+        Dir8::ALL[(*self as usize + 1) % 8]
+    }
+
+    /// Rotate an eighth turn counter-clockwise
+    pub fn rotate_ccw(&self) -> Dir8 {
+        Dir8::ALL[(*self as usize + 7) % 8]
+    }
+
+    pub fn opposite(&self) -> Dir8 {
+        Dir8::ALL[(*self as usize + 4) % 8]
+    }
+}
+
+impl From<Dir4> for Dir8 {
+    fn from(dir: Dir4) -> Self {
+        Dir8::ALL[dir as usize * 2]
+    }
+}
+
+impl From<Dir4> for (isize, isize) {
+    /// Grid step for this direction
+    fn from(dir: Dir4) -> Self {
+        Dir8::from(dir).into()
+    }
+}
+
+impl From<Dir8> for (isize, isize) {
+    /// Grid step for this direction
+    fn from(dir: Dir8) -> Self {
+        let offset = dir.offset();
+        (offset.x as isize, offset.y as isize)
+    }
+}
+
+impl Add<Dir4> for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Dir4) -> Self::Output {
+        self + rhs.offset()
+    }
+}
+
+impl Add<Dir8> for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Dir8) -> Self::Output {
+        self + rhs.offset()
+    }
+}
+
+fn invalid_direction(token: &str) -> ParseError {
+    ParseError::new(1, ParseErrorKind::InvalidValue(token.to_string()))
+}
+
+impl TryFrom<char> for Dir4 {
+    type Error = ParseError;
+
+    /// Accepts both U/R/D/L and N/E/S/W
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            'U' | 'N' => Ok(Dir4::North),
+            'R' | 'E' => Ok(Dir4::East),
+            'D' | 'S' => Ok(Dir4::South),
+            'L' | 'W' => Ok(Dir4::West),
+            _ => Err(invalid_direction(&c.to_string())),
+        }
+    }
+}
+
+impl FromStr for Dir4 {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Dir4::try_from(c),
+            _ => Err(invalid_direction(s)),
+        }
+    }
+}
+
+impl FromStr for Dir8 {
+    type Err = ParseError;
+
+    /// Accepts U/R/D/L, N/E/S/W and two-letter diagonals like "NE" or "UR"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let dirs = s
+            .chars()
+            .map(Dir4::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid_direction(s))?;
+
+        match dirs[..] {
+            [dir] => Ok(dir.into()),
+            [first, second] => {
+                let offset = first.offset() + second.offset();
+                Dir8::ALL
+                    .into_iter()
+                    .find(|dir| dir.offset() == offset && first != second)
+                    .ok_or_else(|| invalid_direction(s))
+            }
+            _ => Err(invalid_direction(s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_arithmetic() {
+        let mut point = Point::new(3, -2);
+        assert_eq!(point + Point::new(1, 1), Point::new(4, -1));
+        assert_eq!(point - Point::new(1, 1), Point::new(2, -3));
+        assert_eq!(-point, Point::new(-3, 2));
+        assert_eq!(point * 3, Point::new(9, -6));
+        assert_eq!(point + Dir4::North, Point::new(3, -3));
+        assert_eq!(point + Dir8::SouthWest, Point::new(2, -1));
+
+        point += Point::new(1, 2);
+        point -= Point::new(0, 1);
+        assert_eq!(point, Point::new(4, -1));
+    }
+
+    #[rstest]
+    #[case(Point::new(0, 0), Point::new(3, 4), 7, 4)]
+    #[case(Point::new(-2, 5), Point::new(1, 5), 3, 3)]
+    #[case(Point::new(1, 1), Point::new(1, 1), 0, 0)]
+    fn test_distances(
+        #[case] a: Point,
+        #[case] b: Point,
+        #[case] manhattan: u64,
+        #[case] chebyshev: u64,
+    ) {
+        assert_eq!(a.manhattan(b), manhattan);
+        assert_eq!(a.chebyshev(b), chebyshev);
+    }
+
+    #[test]
+    fn test_signum() {
+        assert_eq!(Point::new(-7, 0).signum(), Point::new(-1, 0));
+        assert_eq!(Point::new(2, 5).signum(), Point::new(1, 1));
+    }
+
+    #[test]
+    fn test_rotation() {
+        assert_eq!(Dir4::North.offset().rotate_cw(), Dir4::East.offset());
+        assert_eq!(Dir4::North.offset().rotate_ccw(), Dir4::West.offset());
+        assert_eq!(Dir4::West.rotate_cw(), Dir4::North);
+        assert_eq!(Dir4::North.rotate_ccw(), Dir4::West);
+        assert_eq!(Dir4::East.opposite(), Dir4::West);
+        assert_eq!(Dir8::NorthWest.rotate_cw(), Dir8::North);
+        assert_eq!(Dir8::North.rotate_ccw(), Dir8::NorthWest);
+        assert_eq!(Dir8::NorthEast.opposite(), Dir8::SouthWest);
+
+        Dir8::ALL.iter().for_each(|dir| {
+            assert_eq!(
+                dir.rotate_cw().rotate_cw().offset(),
+                dir.offset().rotate_cw()
+            );
+        });
+    }
+
+    #[rstest]
+    #[case("U", Dir4::North)]
+    #[case("N", Dir4::North)]
+    #[case("R", Dir4::East)]
+    #[case("E", Dir4::East)]
+    #[case("D", Dir4::South)]
+    #[case("S", Dir4::South)]
+    #[case("L", Dir4::West)]
+    #[case("W", Dir4::West)]
+    fn test_dir4_from_str(#[case] input: &str, #[case] expected: Dir4) {
+        assert_eq!(input.parse(), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("N", Ok(Dir8::North))]
+    #[case("NE", Ok(Dir8::NorthEast))]
+    #[case("DL", Ok(Dir8::SouthWest))]
+    #[case("NS", Err(invalid_direction("NS")))]
+    #[case("X", Err(invalid_direction("X")))]
+    fn test_dir8_from_str(#[case] input: &str, #[case] expected: Result<Dir8, ParseError>) {
+        assert_eq!(input.parse(), expected);
+    }
+
+    #[test]
+    fn test_invalid_dir4() {
+        assert_eq!("UP".parse::<Dir4>(), Err(invalid_direction("UP")));
+        assert_eq!(Dir4::try_from('x'), Err(invalid_direction("x")));
+    }
+
+    #[test]
+    fn test_grid_conversion() {
+        assert_eq!(Point::new(2, 3).to_grid(), Some((2, 3)));
+        assert_eq!(Point::new(-1, 3).to_grid(), None);
+        assert_eq!(Point::from((4_usize, 5_usize)), Point::new(4, 5));
+        assert_eq!(<(isize, isize)>::from(Dir4::West), (-1, 0));
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use super::{
+    geometry::{Dir4, Dir8},
+    parsing::{ParseError, ParseErrorKind},
+};
+
+/// A dense, rectangular 2D grid addressed by `(x, y)` with the origin at the top left
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Build a grid from row-major cells. Returns `None` if the cell count doesn't match.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Option<Grid<T>> {
+        (cells.len() == width * height).then_some(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// A grid with every cell set to the same value
+    pub fn filled(width: usize, height: usize, value: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// Parse a grid with one cell per character, e.g. a block of digits.
+    /// Errors carry the line and column of the offending character or ragged row.
+    pub fn parse(input: &str, cell: impl Fn(char) -> Option<T>) -> Result<Grid<T>, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+
+        for (y, line) in input.lines().enumerate() {
+            let row_start = cells.len();
+            for (x, c) in line.chars().enumerate() {
+                let value = cell(c).ok_or_else(|| {
+                    ParseError::new(x + 1, ParseErrorKind::InvalidValue(c.to_string()))
+                        .on_line(y + 1)
+                })?;
+                cells.push(value);
+            }
+
+            let row_width = cells.len() - row_start;
+            match width {
+                None => width = Some(row_width),
+                Some(expected) if expected != row_width => {
+                    return Err(ParseError::new(
+                        1,
+                        ParseErrorKind::WrongCount {
+                            expected,
+                            found: row_width,
+                        },
+                    )
+                    .on_line(y + 1));
+                }
+                _ => {}
+            }
+            height += 1;
+        }
+
+        Ok(Grid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, (x, y): (usize, usize)) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, loc: (usize, usize)) -> Option<&T> {
+        self.in_bounds(loc)
+            .then(|| &self.cells[loc.1 * self.width + loc.0])
+    }
+
+    pub fn get_mut(&mut self, loc: (usize, usize)) -> Option<&mut T> {
+        if self.in_bounds(loc) {
+            Some(&mut self.cells[loc.1 * self.width + loc.0])
+        } else {
+            None
+        }
+    }
+
+    /// Move from a location by an offset, if the result is still on the grid
+    pub fn offset(
+        &self,
+        (x, y): (usize, usize),
+        (dx, dy): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let loc = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        self.in_bounds(loc).then_some(loc)
+    }
+
+    /// All locations, row by row
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |index| (index % width, index / width))
+    }
+
+    /// All cells with their locations, row by row
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// In-bounds orthogonal neighbours of a location, clockwise from north
+    pub fn neighbours4(&self, loc: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        Dir4::ALL
+            .into_iter()
+            .filter_map(move |dir| self.offset(loc, dir.into()))
+    }
+
+    /// In-bounds orthogonal and diagonal neighbours of a location, clockwise from north
+    pub fn neighbours8(&self, loc: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        Dir8::ALL
+            .into_iter()
+            .filter_map(move |dir| self.offset(loc, dir.into()))
+    }
+
+    /// Locations from a starting point (exclusive) stepping in a direction until leaving the grid
+    pub fn ray(
+        &self,
+        start: (usize, usize),
+        step: (isize, isize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        std::iter::successors(self.offset(start, step), move |&loc| self.offset(loc, step))
+            .take_while(move |_| step != (0, 0))
+    }
+
+    /// Panics if `y` is outside the grid
+    pub fn row(&self, y: usize) -> &[T] {
+        assert!(y < self.height, "row {} out of bounds", y);
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    /// Panics if `x` is outside the grid
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {} out of bounds", x);
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// Convert every cell, keeping the shape
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, loc: (usize, usize)) -> &Self::Output {
+        self.get(loc).expect("Location out of bounds")
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, loc: (usize, usize)) -> &mut Self::Output {
+        self.get_mut(loc).expect("Location out of bounds")
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    /// Render one line per row, with no separator between cells
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn example_grid() -> Grid<u32> {
+        Grid::parse("123\n456\n789", |c| c.to_digit(10)).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            example_grid(),
+            Grid::new(3, 3, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case("12\n3x", ParseError::new(2, ParseErrorKind::InvalidValue("x".to_string())).on_line(2))]
+    #[case("123\n45\n", ParseError::new(1, ParseErrorKind::WrongCount { expected: 3, found: 2 }).on_line(2))]
+    fn test_parse_errors(#[case] input: &str, #[case] expected: ParseError) {
+        assert_eq!(Grid::parse(input, |c| c.to_digit(10)), Err(expected));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let grid = example_grid();
+        assert_eq!(grid.get((2, 1)), Some(&6));
+        assert_eq!(grid.get((3, 1)), None);
+        assert_eq!(grid.get((0, 3)), None);
+        assert_eq!(grid[(1, 2)], 8);
+    }
+
+    #[rstest]
+    #[case((0, 0), vec![(1, 0), (0, 1)])]
+    #[case((1, 1), vec![(1, 0), (2, 1), (1, 2), (0, 1)])]
+    #[case((2, 2), vec![(2, 1), (1, 2)])]
+    fn test_neighbours4(#[case] loc: (usize, usize), #[case] expected: Vec<(usize, usize)>) {
+        assert_eq!(
+            example_grid().neighbours4(loc).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_neighbours8() {
+        let grid = example_grid();
+        assert_eq!(
+            grid.neighbours8((0, 0)).collect::<Vec<_>>(),
+            vec![(1, 0), (1, 1), (0, 1)]
+        );
+        assert_eq!(grid.neighbours8((1, 1)).count(), 8);
+    }
+
+    #[rstest]
+    #[case((0, 0), (1, 0), vec![(1, 0), (2, 0)])]
+    #[case((2, 2), (-1, -1), vec![(1, 1), (0, 0)])]
+    #[case((1, 0), (0, -1), vec![])]
+    #[case((1, 1), (0, 0), vec![])]
+    fn test_ray(
+        #[case] start: (usize, usize),
+        #[case] step: (isize, isize),
+        #[case] expected: Vec<(usize, usize)>,
+    ) {
+        assert_eq!(
+            example_grid().ray(start, step).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = example_grid();
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(grid.column(2).copied().collect::<Vec<_>>(), vec![3, 6, 9]);
+        assert_eq!(grid.rows().count(), 3);
+        assert_eq!(
+            grid.columns()
+                .map(|column| column.sum::<u32>())
+                .collect::<Vec<_>>(),
+            vec![12, 15, 18]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "column 3 out of bounds")]
+    fn test_column_out_of_bounds() {
+        example_grid().column(3).count();
+    }
+
+    #[test]
+    #[should_panic(expected = "row 3 out of bounds")]
+    fn test_row_out_of_bounds() {
+        example_grid().row(3);
+    }
+
+    #[test]
+    fn test_display() {
+        let grid = example_grid().map(|&n| if n % 2 == 0 { '#' } else { '.' });
+        assert_eq!(grid.to_string(), ".#.\n#.#\n.#.");
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+
+/// An inclusive range of integers, `start..=end`, with `start <= end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    /// Returns `None` for an empty interval, i.e. when `start > end`
+    pub fn new(start: i64, end: i64) -> Option<Interval> {
+        (start <= end).then_some(Interval { start, end })
+    }
+
+    /// Number of integers covered, always at least 1
+    pub fn size(&self) -> u64 {
+        self.start.abs_diff(self.end) + 1
+    }
+
+    pub fn contains(&self, point: i64) -> bool {
+        self.start <= point && point <= self.end
+    }
+
+    /// Whether the other interval lies entirely within this one
+    pub fn contains_interval(&self, other: &Interval) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Whether the intervals share at least one point
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// Whether the intervals overlap or sit directly next to each other, so they merge into one
+    fn touches(&self, other: &Interval) -> bool {
+        self.start <= other.end.saturating_add(1) && other.start <= self.end.saturating_add(1)
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        Interval::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    pub fn union(&self, other: &Interval) -> RangeSet {
+        RangeSet::from_iter([*self, *other])
+    }
+
+    /// The points of this interval that are not in the other one
+    pub fn difference(&self, other: &Interval) -> RangeSet {
+        if !self.overlaps(other) {
+            return RangeSet::from_iter([*self]);
+        }
+
+        RangeSet::from_iter(
+            [
+                Interval::new(self.start, other.start.saturating_sub(1)),
+                Interval::new(other.end.saturating_add(1), self.end),
+            ]
+            .into_iter()
+            .flatten(),
+        )
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// A set of integers stored as sorted, disjoint, non-adjacent intervals
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeSet {
+    intervals: Vec<Interval>,
+}
+
+impl RangeSet {
+    pub fn new() -> RangeSet {
+        RangeSet::default()
+    }
+
+    /// The normalized intervals, in ascending order
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Total number of integers covered
+    pub fn len(&self) -> u64 {
+        self.intervals.iter().map(Interval::size).sum()
+    }
+
+    pub fn contains(&self, point: i64) -> bool {
+        let index = self.intervals.partition_point(|i| i.end < point);
+        self.intervals
+            .get(index)
+            .is_some_and(|interval| interval.contains(point))
+    }
+
+    /// Add an interval, merging it with any that it overlaps or touches
+    pub fn insert(&mut self, interval: Interval) {
+        let first = self
+            .intervals
+            .partition_point(|i| i.end.saturating_add(1) < interval.start);
+        let last = self.intervals[first..]
+            .iter()
+            .take_while(|i| i.touches(&interval))
+            .count();
+
+        let merged = self.intervals[first..first + last]
+            .iter()
+            .fold(interval, |acc, i| Interval {
+                start: acc.start.min(i.start),
+                end: acc.end.max(i.end),
+            });
+        self.intervals.splice(first..first + last, [merged]);
+    }
+
+    pub fn union(&self, other: &RangeSet) -> RangeSet {
+        let mut result = self.clone();
+        other.intervals.iter().for_each(|&i| result.insert(i));
+        result
+    }
+
+    pub fn intersection(&self, other: &RangeSet) -> RangeSet {
+        RangeSet::from_iter(self.intervals.iter().flat_map(|a| {
+            other
+                .intervals
+                .iter()
+                .filter_map(move |b| a.intersection(b))
+        }))
+    }
+
+    pub fn difference(&self, other: &RangeSet) -> RangeSet {
+        other
+            .intervals
+            .iter()
+            .fold(self.clone(), |remaining, removed| {
+                RangeSet::from_iter(
+                    remaining
+                        .intervals
+                        .iter()
+                        .flat_map(|i| i.difference(removed).intervals),
+                )
+            })
+    }
+}
+
+impl FromIterator<Interval> for RangeSet {
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> Self {
+        let mut set = RangeSet::new();
+        iter.into_iter().for_each(|i| set.insert(i));
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn iv(start: i64, end: i64) -> Interval {
+        Interval::new(start, end).unwrap()
+    }
+
+    fn set(intervals: &[(i64, i64)]) -> RangeSet {
+        intervals.iter().map(|&(s, e)| iv(s, e)).collect()
+    }
+
+    #[test]
+    fn test_interval_basics() {
+        assert_eq!(Interval::new(3, 2), None);
+        assert_eq!(iv(2, 2).size(), 1);
+        assert_eq!(iv(-3, 4).size(), 8);
+        assert!(iv(2, 5).contains(5));
+        assert!(!iv(2, 5).contains(6));
+        assert_eq!(iv(2, 8).to_string(), "2-8");
+    }
+
+    #[rstest]
+    #[case(iv(2, 8), iv(3, 7), Some(iv(3, 7)))]
+    #[case(iv(5, 7), iv(7, 9), Some(iv(7, 7)))]
+    #[case(iv(2, 4), iv(6, 8), None)]
+    fn test_intersection(
+        #[case] a: Interval,
+        #[case] b: Interval,
+        #[case] expected: Option<Interval>,
+    ) {
+        assert_eq!(a.intersection(&b), expected);
+        assert_eq!(a.overlaps(&b), expected.is_some());
+    }
+
+    #[rstest]
+    #[case(iv(2, 4), iv(3, 8), set(&[(2, 8)]))]
+    #[case(iv(2, 4), iv(5, 8), set(&[(2, 8)]))]
+    #[case(iv(2, 4), iv(6, 8), set(&[(2, 4), (6, 8)]))]
+    fn test_union(#[case] a: Interval, #[case] b: Interval, #[case] expected: RangeSet) {
+        assert_eq!(a.union(&b), expected);
+    }
+
+    #[rstest]
+    #[case(iv(2, 8), iv(4, 5), set(&[(2, 3), (6, 8)]))]
+    #[case(iv(2, 8), iv(0, 5), set(&[(6, 8)]))]
+    #[case(iv(2, 8), iv(1, 9), set(&[]))]
+    #[case(iv(2, 8), iv(10, 12), set(&[(2, 8)]))]
+    fn test_difference(#[case] a: Interval, #[case] b: Interval, #[case] expected: RangeSet) {
+        assert_eq!(a.difference(&b), expected);
+    }
+
+    #[test]
+    fn test_range_set_normalizes() {
+        let ranges = set(&[(10, 12), (1, 3), (5, 6), (2, 4), (14, 20), (7, 7)]);
+        assert_eq!(ranges.intervals(), &[iv(1, 7), iv(10, 12), iv(14, 20)]);
+        assert_eq!(ranges.len(), 17);
+    }
+
+    #[test]
+    fn test_range_set_insert_bridges_gaps() {
+        let mut ranges = set(&[(1, 2), (5, 6), (9, 10)]);
+        ranges.insert(iv(3, 8));
+        assert_eq!(ranges.intervals(), &[iv(1, 10)]);
+    }
+
+    #[rstest]
+    #[case(0, false)]
+    #[case(1, true)]
+    #[case(4, false)]
+    #[case(12, true)]
+    #[case(13, false)]
+    fn test_range_set_contains(#[case] point: i64, #[case] expected: bool) {
+        assert_eq!(set(&[(1, 3), (10, 12)]).contains(point), expected);
+    }
+
+    #[test]
+    fn test_range_set_operations() {
+        let a = set(&[(1, 5), (10, 15)]);
+        let b = set(&[(4, 11), (20, 21)]);
+
+        assert_eq!(a.union(&b), set(&[(1, 15), (20, 21)]));
+        assert_eq!(a.intersection(&b), set(&[(4, 5), (10, 11)]));
+        assert_eq!(a.difference(&b), set(&[(1, 3), (12, 15)]));
+        assert!(a.difference(&a).is_empty());
+    }
+}
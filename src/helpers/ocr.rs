@@ -0,0 +1,156 @@
+use std::fmt;
+
+use super::grid::Grid;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+
+/// Glyphs sit side by side with one blank column between them
+const GLYPH_SPACING: usize = GLYPH_WIDTH + 1;
+
+/// The 4x6 capital letters drawn by the puzzles, rows joined with newlines
+const GLYPHS: [(char, &str); 17] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', ".###\n..#.\n..#.\n..#.\n..#.\n.###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+    /// The screen isn't a whole number of glyphs
+    WrongSize { width: usize, height: usize },
+    /// The glyph at `index` (counting from the left) isn't a known letter
+    UnknownGlyph { index: usize, bitmap: String },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::WrongSize { width, height } => write!(
+                f,
+                "a {}x{} screen doesn't hold a row of {}x{} glyphs",
+                width, height, GLYPH_WIDTH, GLYPH_HEIGHT
+            ),
+            OcrError::UnknownGlyph { index, bitmap } => {
+                write!(f, "unknown glyph at position {}:\n{}", index, bitmap)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// Render the glyph starting at column `left` with `#` for lit pixels and `.` for dark ones
+fn glyph_bitmap(screen: &Grid<bool>, left: usize) -> String {
+    (0..GLYPH_HEIGHT)
+        .map(|y| {
+            (left..left + GLYPH_WIDTH)
+                .map(|x| if screen[(x, y)] { '#' } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Read a row of letters off a screen where lit pixels are `true`.
+/// The blank column after the last glyph is optional.
+pub fn recognize(screen: &Grid<bool>) -> Result<String, OcrError> {
+    let trailing = screen.width() % GLYPH_SPACING;
+    if screen.height() != GLYPH_HEIGHT || (trailing != 0 && trailing != GLYPH_WIDTH) {
+        return Err(OcrError::WrongSize {
+            width: screen.width(),
+            height: screen.height(),
+        });
+    }
+
+    (0..(screen.width() + 1) / GLYPH_SPACING)
+        .map(|index| {
+            let bitmap = glyph_bitmap(screen, index * GLYPH_SPACING);
+            GLYPHS
+                .iter()
+                .find(|(_, glyph)| *glyph == bitmap)
+                .map(|&(letter, _)| letter)
+                .ok_or(OcrError::UnknownGlyph { index, bitmap })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn screen(rows: &[&str]) -> Grid<bool> {
+        Grid::parse(&rows.join("\n"), |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_recognize_every_glyph() {
+        for (letter, glyph) in GLYPHS {
+            let rows = glyph.lines().collect::<Vec<_>>();
+            assert_eq!(recognize(&screen(&rows)), Ok(letter.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_recognize_word() {
+        let screen = screen(&[
+            "###...##..#....###..",
+            "#..#.#..#.#....#..#.",
+            "#..#.#....#....###..",
+            "###..#.##.#....#..#.",
+            "#.#..#..#.#....#..#.",
+            "#..#..###.####.###..",
+        ]);
+        assert_eq!(recognize(&screen), Ok("RGLB".to_string()));
+    }
+
+    #[test]
+    fn test_unknown_glyph() {
+        let screen = screen(&[
+            ".##..#..#",
+            "#..#.##.#",
+            "#..#.#.##",
+            "####.#..#",
+            "#..#.#..#",
+            "#..#.#..#",
+        ]);
+        assert_eq!(
+            recognize(&screen),
+            Err(OcrError::UnknownGlyph {
+                index: 1,
+                bitmap: "#..#\n##.#\n#.##\n#..#\n#..#\n#..#".to_string(),
+            })
+        );
+    }
+
+    #[rstest]
+    #[case(&["####"; 5])]
+    #[case(&["######"; 6])]
+    #[case(&["#######"; 6])]
+    fn test_wrong_size(#[case] rows: &[&str]) {
+        assert!(matches!(
+            recognize(&screen(rows)),
+            Err(OcrError::WrongSize { .. })
+        ));
+    }
+}
@@ -0,0 +1,416 @@
+use std::{fmt, str::FromStr};
+
+/// What went wrong while parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A literal part of a pattern was not found
+    ExpectedLiteral(String),
+    /// A pattern placeholder matched nothing
+    EmptyCapture,
+    /// Text was left over after the end of a pattern
+    TrailingInput(String),
+    /// A token could not be converted to the requested type
+    InvalidValue(String),
+    /// A pattern produced a different number of values than requested
+    WrongCount { expected: usize, found: usize },
+}
+
+/// A parse failure, located by 1-based line and column. Columns count characters, not bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub fn new(column: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: 1,
+            column,
+            kind,
+        }
+    }
+
+    /// Relocate an error from a single-line parser onto a line of a larger input
+    pub fn on_line(self, line: usize) -> ParseError {
+        ParseError { line, ..self }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::ExpectedLiteral(literal) => write!(f, "expected \"{}\"", literal),
+            ParseErrorKind::EmptyCapture => write!(f, "expected a value"),
+            ParseErrorKind::TrailingInput(rest) => write!(f, "unexpected trailing \"{}\"", rest),
+            ParseErrorKind::InvalidValue(token) => write!(f, "invalid value \"{}\"", token),
+            ParseErrorKind::WrongCount { expected, found } => {
+                write!(f, "expected {} values, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The 1-based character column of a byte offset into a line
+fn column_at(line: &str, offset: usize) -> usize {
+    line[..offset].chars().count() + 1
+}
+
+/// Parse a token, reporting failures at the given 1-based column
+fn parse_token<T: FromStr>(token: &str, column: usize) -> Result<T, ParseError> {
+    token
+        .parse()
+        .map_err(|_| ParseError::new(column, ParseErrorKind::InvalidValue(token.to_string())))
+}
+
+/// Find runs of digits, optionally with a leading '-', as (column, token) pairs
+fn integer_tokens(line: &str, signed: bool) -> Vec<(usize, &str)> {
+    let bytes = line.as_bytes();
+    let mut tokens = vec![];
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let start = index;
+        if signed && bytes[index] == b'-' {
+            index += 1;
+        }
+        let digits_start = index;
+        while index < bytes.len() && bytes[index].is_ascii_digit() {
+            index += 1;
+        }
+
+        if index > digits_start {
+            tokens.push((column_at(line, start), &line[start..index]));
+        } else {
+            index = start + 1;
+        }
+    }
+
+    tokens
+}
+
+/// Extract all integers from a line, treating a '-' directly before digits as a sign
+pub fn signed_integers<T: FromStr>(line: &str) -> Result<Vec<T>, ParseError> {
+    integer_tokens(line, true)
+        .into_iter()
+        .map(|(column, token)| parse_token(token, column))
+        .collect()
+}
+
+/// Extract all runs of digits from a line, treating '-' as a separator (e.g. "2-4")
+pub fn unsigned_integers<T: FromStr>(line: &str) -> Result<Vec<T>, ParseError> {
+    integer_tokens(line, false)
+        .into_iter()
+        .map(|(column, token)| parse_token(token, column))
+        .collect()
+}
+
+/// A group of consecutive non-blank lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block<'a> {
+    /// 1-based line number of the first line in the block
+    pub line: usize,
+    pub lines: Vec<&'a str>,
+}
+
+/// Split input into blocks separated by one or more blank lines
+pub fn blocks(input: &str) -> Vec<Block<'_>> {
+    let mut result: Vec<Block> = vec![];
+    let mut current: Option<Block> = None;
+
+    input.lines().enumerate().for_each(|(index, line)| {
+        if line.trim().is_empty() {
+            result.extend(current.take());
+        } else {
+            current
+                .get_or_insert(Block {
+                    line: index + 1,
+                    lines: vec![],
+                })
+                .lines
+                .push(line);
+        }
+    });
+    result.extend(current);
+
+    result
+}
+
+/// Run a single-line parser over every line of the input, tagging errors with their line number
+pub fn parse_lines<T>(
+    input: &str,
+    parser: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| parser(line).map_err(|e| e.on_line(index + 1)))
+        .collect()
+}
+
+/// A line format with `{}` placeholders, e.g. `"move {} from {} to {}"`
+#[derive(Debug, Clone)]
+pub struct Pattern<'p> {
+    literals: Vec<&'p str>,
+}
+
+impl<'p> Pattern<'p> {
+    pub fn new(template: &'p str) -> Pattern<'p> {
+        Pattern {
+            literals: template.split("{}").collect(),
+        }
+    }
+
+    /// Number of placeholders in the pattern
+    pub fn arity(&self) -> usize {
+        self.literals.len() - 1
+    }
+
+    /// Match a line, returning the text of each placeholder with its 1-based column
+    pub fn captures<'l>(&self, line: &'l str) -> Result<Vec<(usize, &'l str)>, ParseError> {
+        let expect_literal = |pos: usize, literal: &str| {
+            ParseError::new(
+                column_at(line, pos),
+                ParseErrorKind::ExpectedLiteral(literal.to_string()),
+            )
+        };
+
+        let first = self.literals[0];
+        if !line.starts_with(first) {
+            return Err(expect_literal(0, first));
+        }
+
+        let mut pos = first.len();
+        let mut captures = vec![];
+
+        for (index, &literal) in self.literals.iter().enumerate().skip(1) {
+            let is_last = index == self.literals.len() - 1;
+            let rest = &line[pos..];
+
+            let capture_len = if literal.is_empty() {
+                // two adjacent placeholders are ambiguous, so split on whitespace in that case.
+                if is_last {
+                    rest.len()
+                } else {
+                    rest.find(char::is_whitespace).unwrap_or(rest.len())
+                }
+            } else if is_last {
+                rest.rfind(literal)
+                    .filter(|&at| at + literal.len() == rest.len())
+                    .ok_or_else(|| expect_literal(pos, literal))?
+            } else {
+                rest.find(literal)
+                    .ok_or_else(|| expect_literal(pos, literal))?
+            };
+
+            if capture_len == 0 {
+                return Err(ParseError::new(
+                    column_at(line, pos),
+                    ParseErrorKind::EmptyCapture,
+                ));
+            }
+
+            captures.push((column_at(line, pos), &rest[..capture_len]));
+            pos += capture_len + literal.len();
+        }
+
+        if pos < line.len() {
+            return Err(ParseError::new(
+                column_at(line, pos),
+                ParseErrorKind::TrailingInput(line[pos..].to_string()),
+            ));
+        }
+
+        Ok(captures)
+    }
+
+    /// Match a line and parse every placeholder as the same type
+    pub fn parse<T: FromStr>(&self, line: &str) -> Result<Vec<T>, ParseError> {
+        self.captures(line)?
+            .into_iter()
+            .map(|(column, token)| parse_token(token, column))
+            .collect()
+    }
+
+    /// Match a line and parse exactly `N` placeholders, for destructuring
+    pub fn parse_array<T: FromStr, const N: usize>(
+        &self,
+        line: &str,
+    ) -> Result<[T; N], ParseError> {
+        self.parse(line)?.try_into().map_err(|values: Vec<T>| {
+            ParseError::new(
+                1,
+                ParseErrorKind::WrongCount {
+                    expected: N,
+                    found: values.len(),
+                },
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("move 3 from 1 to 2", vec![3, 1, 2])]
+    #[case("x=-12, y=7", vec![-12, 7])]
+    #[case("2-4,6-8", vec![2, -4, 6, -8])]
+    #[case("no numbers - here", vec![])]
+    fn test_signed_integers(#[case] line: &str, #[case] expected: Vec<i64>) {
+        assert_eq!(signed_integers::<i64>(line), Ok(expected));
+    }
+
+    #[test]
+    fn test_unsigned_integers() {
+        assert_eq!(unsigned_integers::<u32>("2-4,6-8"), Ok(vec![2, 4, 6, 8]));
+    }
+
+    #[test]
+    fn test_integer_overflow() {
+        assert_eq!(
+            signed_integers::<u8>("ok 12, too big 300"),
+            Err(ParseError::new(
+                16,
+                ParseErrorKind::InvalidValue("300".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_columns_count_characters() {
+        assert_eq!(
+            signed_integers::<u8>("é → 300"),
+            Err(ParseError::new(
+                5,
+                ParseErrorKind::InvalidValue("300".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(
+            blocks("1000\n2000\n\n\n3000\n\n4000\n"),
+            vec![
+                Block {
+                    line: 1,
+                    lines: vec!["1000", "2000"]
+                },
+                Block {
+                    line: 5,
+                    lines: vec!["3000"]
+                },
+                Block {
+                    line: 7,
+                    lines: vec!["4000"]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        assert_eq!(
+            parse_lines("1 2\n3 x4\n5 -", |line| {
+                let pattern = Pattern::new("{} {}");
+                pattern.parse_array::<u32, 2>(line)
+            }),
+            Err(ParseError {
+                line: 2,
+                column: 3,
+                kind: ParseErrorKind::InvalidValue("x4".to_string())
+            })
+        );
+    }
+
+    #[rstest]
+    #[case("move {} from {} to {}", "move 3 from 1 to 2", vec![(6, "3"), (13, "1"), (18, "2")])]
+    #[case("{}-{},{}-{}", "2-4,6-8", vec![(1, "2"), (3, "4"), (5, "6"), (7, "8")])]
+    #[case("{} {}", "addx -5", vec![(1, "addx"), (6, "-5")])]
+    #[case("$ cd {}", "$ cd ..", vec![(6, "..")])]
+    #[case("{} → {}", "é → x", vec![(1, "é"), (5, "x")])]
+    fn test_pattern_captures(
+        #[case] template: &str,
+        #[case] line: &str,
+        #[case] expected: Vec<(usize, &str)>,
+    ) {
+        assert_eq!(Pattern::new(template).captures(line), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("move 3 frm 1 to 2", ParseError::new(6, ParseErrorKind::ExpectedLiteral(" from ".to_string())))]
+    #[case("mve 3 from 1 to 2", ParseError::new(1, ParseErrorKind::ExpectedLiteral("move ".to_string())))]
+    #[case("move  from 1 to 2", ParseError::new(6, ParseErrorKind::EmptyCapture))]
+    #[case("move 3 from 1 to x", ParseError::new(18, ParseErrorKind::InvalidValue("x".to_string())))]
+    fn test_pattern_errors(#[case] line: &str, #[case] expected: ParseError) {
+        let pattern = Pattern::new("move {} from {} to {}");
+        assert_eq!(pattern.parse::<u32>(line), Err(expected));
+    }
+
+    #[test]
+    fn test_pattern_trailing_input() {
+        assert_eq!(
+            Pattern::new("noop").captures("noop 1"),
+            Err(ParseError::new(
+                5,
+                ParseErrorKind::TrailingInput(" 1".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_pattern_extra_input_after_placeholder() {
+        assert_eq!(
+            Pattern::new("{},{}").parse::<u32>("1,2,3"),
+            Err(ParseError::new(
+                3,
+                ParseErrorKind::InvalidValue("2,3".to_string())
+            ))
+        );
+        assert_eq!(
+            Pattern::new("<{}>").captures("<1>!"),
+            Err(ParseError::new(
+                2,
+                ParseErrorKind::ExpectedLiteral(">".to_string())
+            ))
+        );
+        assert_eq!(
+            Pattern::new("{} items").captures("3 items left"),
+            Err(ParseError::new(
+                1,
+                ParseErrorKind::ExpectedLiteral(" items".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_pattern_parse_array() {
+        let pattern = Pattern::new("move {} from {} to {}");
+        let [quantity, from, to] = pattern.parse_array::<u32, 3>("move 3 from 1 to 2").unwrap();
+        assert_eq!((quantity, from, to), (3, 1, 2));
+
+        assert_eq!(
+            pattern.parse_array::<u32, 2>("move 3 from 1 to 2"),
+            Err(ParseError::new(
+                1,
+                ParseErrorKind::WrongCount {
+                    expected: 2,
+                    found: 3
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_error_display() {
+        let error = ParseError::new(6, ParseErrorKind::EmptyCapture).on_line(4);
+        assert_eq!(error.to_string(), "line 4, column 6: expected a value");
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+use super::{
+    geometry::{Dir4, Point},
+    grid::Grid,
+};
+
+/// Anything that can list a node's neighbours along with the cost of moving to each.
+/// Closures of the form `Fn(&N) -> Vec<(N, u64)>` are graphs too.
+pub trait Graph<N> {
+    fn neighbours(&self, node: &N) -> Vec<(N, u64)>;
+}
+
+impl<N, F> Graph<N> for F
+where
+    F: Fn(&N) -> Vec<(N, u64)>,
+{
+    fn neighbours(&self, node: &N) -> Vec<(N, u64)> {
+        self(node)
+    }
+}
+
+/// A route through a graph, including both endpoints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N> {
+    pub nodes: Vec<N>,
+    pub cost: u64,
+}
+
+/// Walk parent links back from the goal to rebuild the route
+fn reconstruct<N: Clone + Eq + Hash>(parents: &HashMap<N, N>, goal: N, cost: u64) -> Path<N> {
+    let mut nodes = vec![goal];
+    while let Some(parent) = parents.get(nodes.last().unwrap()) {
+        nodes.push(parent.clone());
+    }
+    nodes.reverse();
+
+    Path { nodes, cost }
+}
+
+/// Breadth-first search, ignoring edge costs. The path cost is the number of steps taken.
+pub fn bfs<N: Clone + Eq + Hash>(
+    graph: &impl Graph<N>,
+    start: N,
+    is_goal: impl Fn(&N) -> bool,
+) -> Option<Path<N>> {
+    let mut parents: HashMap<N, N> = HashMap::new();
+    let mut queue = VecDeque::from([(start.clone(), 0)]);
+    let mut seen = HashSet::from([start]);
+
+    while let Some((node, steps)) = queue.pop_front() {
+        if is_goal(&node) {
+            return Some(reconstruct(&parents, node, steps));
+        }
+
+        for (next, _) in graph.neighbours(&node) {
+            if seen.insert(next.clone()) {
+                parents.insert(next.clone(), node.clone());
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+
+    None
+}
+
+/// Cheapest path search, with edge costs
+pub fn dijkstra<N: Clone + Eq + Hash + Ord>(
+    graph: &impl Graph<N>,
+    start: N,
+    is_goal: impl Fn(&N) -> bool,
+) -> Option<Path<N>> {
+    astar(graph, start, is_goal, |_| 0)
+}
+
+/// Cheapest path search guided by a heuristic, which must never overestimate the remaining cost
+pub fn astar<N: Clone + Eq + Hash + Ord>(
+    graph: &impl Graph<N>,
+    start: N,
+    is_goal: impl Fn(&N) -> bool,
+    heuristic: impl Fn(&N) -> u64,
+) -> Option<Path<N>> {
+    let mut parents: HashMap<N, N> = HashMap::new();
+    let mut best: HashMap<N, u64> = HashMap::from([(start.clone(), 0)]);
+    let mut queue = BinaryHeap::from([Reverse((heuristic(&start), 0, start))]);
+
+    while let Some(Reverse((_, cost, node))) = queue.pop() {
+        if is_goal(&node) {
+            return Some(reconstruct(&parents, node, cost));
+        }
+        if best.get(&node).is_some_and(|&known| known < cost) {
+            continue;
+        }
+
+        for (next, step_cost) in graph.neighbours(&node) {
+            let next_cost = cost + step_cost;
+            if best.get(&next).is_none_or(|&known| next_cost < known) {
+                best.insert(next.clone(), next_cost);
+                parents.insert(next.clone(), node.clone());
+                queue.push(Reverse((next_cost + heuristic(&next), next_cost, next)));
+            }
+        }
+    }
+
+    None
+}
+
+/// Orthogonal moves between grid cells. `cost(from, to)` prices a step, or returns `None` if it's blocked.
+pub fn grid_moves<'a, T>(
+    grid: &'a Grid<T>,
+    cost: impl Fn(&T, &T) -> Option<u64> + 'a,
+) -> impl Graph<(usize, usize)> + 'a {
+    move |&loc: &(usize, usize)| {
+        grid.neighbours4(loc)
+            .filter_map(|next| cost(&grid[loc], &grid[next]).map(|c| (next, c)))
+            .collect::<Vec<_>>()
+    }
+}
+
+/// Like `grid_moves`, with nodes addressed as points
+pub fn point_moves<'a, T>(
+    grid: &'a Grid<T>,
+    cost: impl Fn(&T, &T) -> Option<u64> + 'a,
+) -> impl Graph<Point> + 'a {
+    move |&point: &Point| {
+        let from = match point.to_grid().and_then(|loc| grid.get(loc)) {
+            Some(from) => from,
+            None => return vec![],
+        };
+
+        Dir4::ALL
+            .into_iter()
+            .map(|dir| point + dir)
+            .filter_map(|next| {
+                let to = grid.get(next.to_grid()?)?;
+                cost(from, to).map(|c| (next, c))
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAZE: &str = "\
+S.#.....
+.##.###.
+....#...
+.####.#.
+......#E";
+
+    fn maze() -> Grid<char> {
+        Grid::parse(MAZE, Some).unwrap()
+    }
+
+    fn find(grid: &Grid<char>, target: char) -> (usize, usize) {
+        grid.iter()
+            .find(|(_, &c)| c == target)
+            .map(|(loc, _)| loc)
+            .unwrap()
+    }
+
+    fn open(_: &char, to: &char) -> Option<u64> {
+        (*to != '#').then_some(1)
+    }
+
+    /// Each path step must be a single orthogonal move onto an open cell
+    fn assert_valid_path(grid: &Grid<char>, path: &Path<(usize, usize)>) {
+        path.nodes.windows(2).for_each(|pair| {
+            assert_eq!(Point::from(pair[0]).manhattan(Point::from(pair[1])), 1);
+            assert_ne!(grid[pair[1]], '#');
+        });
+    }
+
+    #[test]
+    fn test_bfs() {
+        let grid = maze();
+        let end = find(&grid, 'E');
+        let path = bfs(&grid_moves(&grid, open), (0, 0), |&loc| loc == end).unwrap();
+
+        assert_eq!(path.cost, 15);
+        assert_eq!(path.nodes.len(), 16);
+        assert_eq!(path.nodes.first(), Some(&(0, 0)));
+        assert_eq!(path.nodes.last(), Some(&end));
+        assert_valid_path(&grid, &path);
+    }
+
+    #[test]
+    fn test_dijkstra_matches_bfs_on_unit_costs() {
+        let grid = maze();
+        let end = find(&grid, 'E');
+        let path = dijkstra(&grid_moves(&grid, open), (0, 0), |&loc| loc == end).unwrap();
+
+        assert_eq!(path.cost, 15);
+        assert_valid_path(&grid, &path);
+    }
+
+    #[test]
+    fn test_dijkstra_weighted() {
+        // walking through the 9s is shorter but more expensive than going around them
+        let grid = Grid::parse("1911\n1911\n1111", |c| c.to_digit(10)).unwrap();
+        let path = dijkstra(
+            &grid_moves(&grid, |_, &to| Some(to as u64)),
+            (0, 0),
+            |&loc| loc == (3, 0),
+        )
+        .unwrap();
+
+        assert_eq!(path.cost, 7);
+        assert_eq!(
+            path.nodes,
+            vec![
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (1, 2),
+                (2, 2),
+                (2, 1),
+                (2, 0),
+                (3, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_astar_on_points() {
+        let grid = maze();
+        let end = Point::from(find(&grid, 'E'));
+        let path = astar(
+            &point_moves(&grid, open),
+            Point::ORIGIN,
+            |&p| p == end,
+            |p| p.manhattan(end),
+        )
+        .unwrap();
+
+        assert_eq!(path.cost, 15);
+        assert_eq!(path.nodes.last(), Some(&end));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let grid = Grid::parse("S#.\n##.\n..E", Some).unwrap();
+        let moves = grid_moves(&grid, open);
+
+        assert_eq!(bfs(&moves, (0, 0), |&loc| loc == (2, 2)), None);
+        assert_eq!(dijkstra(&moves, (0, 0), |&loc| loc == (2, 2)), None);
+    }
+
+    #[test]
+    fn test_start_is_goal() {
+        let grid = maze();
+        let path = bfs(&grid_moves(&grid, open), (0, 0), |_| true).unwrap();
+        assert_eq!(
+            path,
+            Path {
+                nodes: vec![(0, 0)],
+                cost: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_closure_graph() {
+        // a number line where you can add 1 (cost 1) or double (cost 2)
+        let graph = |&n: &u64| vec![(n + 1, 1), (n * 2, 2)];
+        let path = dijkstra(&graph, 1, |&n| n == 10).unwrap();
+
+        assert_eq!(path.cost, 6);
+        assert_eq!(path.nodes, vec![1, 2, 4, 5, 10]);
+    }
+}
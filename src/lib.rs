@@ -7,6 +7,7 @@ use std::env;
 use std::fs;
 
 pub mod helpers;
+pub mod template;
 
 pub const ANSI_ITALIC: &str = "\x1b[3m";
 pub const ANSI_BOLD: &str = "\x1b[1m";
@@ -0,0 +1,139 @@
+/*
+ * This file contains template code.
+ * There is no need to edit this file unless you want to change template functionality.
+ */
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::{env::temp_dir, fs, io, process::Command};
+
+use super::scaffold::input_path;
+
+/// A failure while downloading puzzle data with aoc-cli
+#[derive(Debug)]
+pub enum DownloadError {
+    AocNotFound,
+    Spawn(io::Error),
+    AocFailed,
+    Copy(io::Error),
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::AocNotFound => write!(
+                f,
+                "command \"aoc\" not found or not callable. Try running \"cargo install aoc-cli\" to install it."
+            ),
+            DownloadError::Spawn(e) => write!(f, "failed to spawn aoc-cli: {}", e),
+            DownloadError::AocFailed => write!(f, "aoc-cli exited with an error"),
+            DownloadError::Copy(e) => {
+                write!(f, "could not copy downloaded input to input file: {}", e)
+            }
+        }
+    }
+}
+
+/// The result of a successful download
+pub struct Download {
+    pub input_path: String,
+    /// Puzzle description, if it was requested
+    pub puzzle: Option<String>,
+}
+
+fn tmp_path(name: &str) -> PathBuf {
+    let mut path = temp_dir();
+    path.push(name);
+    path
+}
+
+fn remove_file(path: &PathBuf) {
+    #[allow(unused_must_use)]
+    {
+        fs::remove_file(path);
+    }
+}
+
+fn run_aoc(
+    day: u8,
+    year: Option<i16>,
+    input_tmp: &PathBuf,
+    puzzle_tmp: Option<&PathBuf>,
+) -> Result<Download, DownloadError> {
+    let input_path = input_path(day);
+
+    // check if aoc binary exists and is callable.
+    if Command::new("aoc").arg("-V").output().is_err() {
+        return Err(DownloadError::AocNotFound);
+    }
+
+    let mut cmd_args = vec![];
+
+    if let Some(year) = year {
+        cmd_args.push("--year".into());
+        cmd_args.push(year.to_string());
+    }
+
+    cmd_args.append(&mut vec![
+        "--input-file".into(),
+        input_tmp.to_string_lossy().to_string(),
+    ]);
+
+    if let Some(puzzle_tmp) = puzzle_tmp {
+        cmd_args.push("--puzzle-file".into());
+        cmd_args.push(puzzle_tmp.to_string_lossy().to_string());
+    }
+
+    cmd_args.append(&mut vec![
+        "--day".into(),
+        day.to_string(),
+        "download".into(),
+    ]);
+
+    println!("Downloading input with >aoc {}", cmd_args.join(" "));
+
+    let cmd_output = Command::new("aoc")
+        .args(cmd_args)
+        .output()
+        .map_err(DownloadError::Spawn)?;
+
+    io::stdout()
+        .write_all(&cmd_output.stdout)
+        .expect("could not write cmd stdout to pipe.");
+    io::stderr()
+        .write_all(&cmd_output.stderr)
+        .expect("could not write cmd stderr to pipe.");
+
+    if !cmd_output.status.success() {
+        return Err(DownloadError::AocFailed);
+    }
+
+    fs::copy(input_tmp, &input_path).map_err(DownloadError::Copy)?;
+
+    Ok(Download {
+        input_path,
+        puzzle: puzzle_tmp.and_then(|path| fs::read_to_string(path).ok()),
+    })
+}
+
+/// Download the input for a day to its input file, optionally fetching the puzzle description too
+pub fn download(day: u8, year: Option<i16>, with_puzzle: bool) -> Result<Download, DownloadError> {
+    // acquire temp file paths to write aoc-cli output to.
+    // aoc-cli expects these files not to be present - delete just in case.
+    let input_tmp = tmp_path("aoc_input_tmp");
+    let puzzle_tmp = tmp_path("aoc_puzzle_tmp");
+    remove_file(&input_tmp);
+    remove_file(&puzzle_tmp);
+
+    let result = run_aoc(
+        day,
+        year,
+        &input_tmp,
+        if with_puzzle { Some(&puzzle_tmp) } else { None },
+    );
+
+    remove_file(&input_tmp);
+    remove_file(&puzzle_tmp);
+
+    result
+}
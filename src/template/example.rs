@@ -0,0 +1,85 @@
+/*
+ * This file contains template code.
+ * There is no need to edit this file unless you want to change template functionality.
+ */
+
+/// A code block found in a puzzle description, with the prose that came before it
+struct CodeBlock {
+    preceding_text: String,
+    contents: String,
+}
+
+/// Collect fenced (```) and indented (4 spaces) code blocks out of a markdown puzzle description
+fn code_blocks(puzzle: &str) -> Vec<CodeBlock> {
+    let mut blocks = vec![];
+    let mut preceding_text = String::new();
+    let mut lines = puzzle.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if line.trim_start().starts_with("```") {
+            let mut contents = vec![];
+            for inner in lines.by_ref() {
+                if inner.trim_start().starts_with("```") {
+                    break;
+                }
+                contents.push(inner);
+            }
+            blocks.push(CodeBlock {
+                preceding_text: std::mem::take(&mut preceding_text),
+                contents: contents.join("\n"),
+            });
+        } else if let Some(first) = line.strip_prefix("    ") {
+            let mut contents = vec![first];
+            while let Some(inner) = lines.peek().and_then(|l| l.strip_prefix("    ")) {
+                contents.push(inner);
+                lines.next();
+            }
+            blocks.push(CodeBlock {
+                preceding_text: std::mem::take(&mut preceding_text),
+                contents: contents.join("\n"),
+            });
+        } else if !line.trim().is_empty() {
+            preceding_text = line.to_string();
+        }
+    }
+
+    blocks
+}
+
+/// Extract the example input from a puzzle description.
+/// Picks the first code block introduced by text mentioning an example, falling back to the first multi-line block.
+pub fn extract_example(puzzle: &str) -> Option<String> {
+    let blocks = code_blocks(puzzle);
+
+    blocks
+        .iter()
+        .find(|block| block.preceding_text.to_lowercase().contains("example"))
+        .or_else(|| blocks.iter().find(|block| block.contents.contains('\n')))
+        .map(|block| block.contents.trim_end().to_string())
+        .filter(|contents| !contents.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        "## --- Day 1 ---\n\nSome prose.\n\nFor example:\n\n```\n1000\n2000\n\n3000\n```\n\nMore prose.\n",
+        Some("1000\n2000\n\n3000")
+    )]
+    #[case(
+        "For example, suppose:\n\n    A Y\n    B X\n    C Z\n\nDone.",
+        Some("A Y\nB X\nC Z")
+    )]
+    #[case(
+        "Intro:\n\n```\nsingle\n```\n\nConsider this example list:\n\n```\n2-4,6-8\n2-3,4-5\n```\n",
+        Some("2-4,6-8\n2-3,4-5")
+    )]
+    #[case("Intro:\n\n```\nfirst\nblock\n```\n", Some("first\nblock"))]
+    #[case("No code blocks here.", None)]
+    fn test_extract_example(#[case] puzzle: &str, #[case] expected: Option<&str>) {
+        assert_eq!(extract_example(puzzle), expected.map(str::to_string));
+    }
+}
@@ -0,0 +1,241 @@
+/*
+ * This file contains template code.
+ * There is no need to edit this file unless you want to change template functionality.
+ */
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+const MODULE_TEMPLATE: &str = r###"pub fn part_one(input: &str) -> Option<u32> {
+    None
+}
+
+pub fn part_two(input: &str) -> Option<u32> {
+    None
+}
+
+fn main() {
+    let input = &advent_of_code::read_file("inputs", DAY);
+    advent_of_code::solve!(1, part_one, input);
+    advent_of_code::solve!(2, part_two, input);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        let input = advent_of_code::read_file("examples", DAY);
+        assert_eq!(part_one(&input), None);
+    }
+
+    #[test]
+    fn test_part_two() {
+        let input = advent_of_code::read_file("examples", DAY);
+        assert_eq!(part_two(&input), None);
+    }
+}
+"###;
+
+/// What scaffolding does (or would do) with a single file
+#[derive(Debug, PartialEq)]
+pub enum FileAction {
+    Create,
+    Overwrite,
+    Skip(&'static str),
+}
+
+/// A file that is part of a scaffolded day
+pub struct ScaffoldFile {
+    pub kind: &'static str,
+    pub path: String,
+    pub contents: String,
+    pub action: FileAction,
+}
+
+impl ScaffoldFile {
+    /// Write the file according to its planned action
+    pub fn write(&self) -> Result<(), std::io::Error> {
+        let mut options = OpenOptions::new();
+        options.write(true);
+
+        match self.action {
+            FileAction::Create => options.create_new(true),
+            FileAction::Overwrite => options.truncate(true),
+            FileAction::Skip(_) => return Ok(()),
+        };
+
+        options
+            .open(&self.path)?
+            .write_all(self.contents.as_bytes())
+    }
+
+    /// Print a line describing what happened to the file
+    pub fn report(&self, dry_run: bool) {
+        let verb = match (&self.action, dry_run) {
+            (FileAction::Create, false) => "Created",
+            (FileAction::Create, true) => "Would create",
+            (FileAction::Overwrite, false) => "Overwrote",
+            (FileAction::Overwrite, true) => "Would overwrite",
+            (FileAction::Skip(_), false) => "Skipped",
+            (FileAction::Skip(_), true) => "Would skip",
+        };
+
+        match &self.action {
+            FileAction::Skip(reason) => {
+                println!("{} {} file \"{}\" ({})", verb, self.kind, self.path, reason)
+            }
+            _ => println!("{} {} file \"{}\"", verb, self.kind, self.path),
+        }
+    }
+}
+
+pub fn input_path(day: u8) -> String {
+    format!("src/inputs/{:02}.txt", day)
+}
+
+pub fn example_path(day: u8) -> String {
+    format!("src/examples/{:02}.txt", day)
+}
+
+pub fn module_path(day: u8) -> String {
+    format!("src/bin/{:02}.rs", day)
+}
+
+/// Decide what to do with the module file. Existing modules are only replaced with `--force`.
+fn plan_module_file(path: &Path, force: bool) -> FileAction {
+    if !path.exists() {
+        FileAction::Create
+    } else if force {
+        FileAction::Overwrite
+    } else {
+        FileAction::Skip("already exists, use --force to overwrite")
+    }
+}
+
+/// Whether a file exists and has contents
+pub fn has_contents(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|meta| meta.len() > 0)
+}
+
+/// Decide what to do with an input or example file. These are never overwritten, even with `--force`.
+fn plan_data_file(path: &Path) -> FileAction {
+    if has_contents(path) {
+        FileAction::Skip("not empty")
+    } else if path.exists() {
+        FileAction::Skip("already exists")
+    } else {
+        FileAction::Create
+    }
+}
+
+/// Plan the module, input and example files for a day
+pub fn plan(day: u8, force: bool) -> [ScaffoldFile; 3] {
+    let module_path = module_path(day);
+    let input_path = input_path(day);
+    let example_path = example_path(day);
+
+    [
+        ScaffoldFile {
+            kind: "module",
+            action: plan_module_file(Path::new(&module_path), force),
+            path: module_path,
+            contents: MODULE_TEMPLATE.replace("DAY", &day.to_string()),
+        },
+        ScaffoldFile {
+            kind: "input",
+            action: plan_data_file(Path::new(&input_path)),
+            path: input_path,
+            contents: String::new(),
+        },
+        ScaffoldFile {
+            kind: "example",
+            action: plan_data_file(Path::new(&example_path)),
+            path: example_path,
+            contents: String::new(),
+        },
+    ]
+}
+
+/// Scaffold a day, reporting each file as it is handled
+pub fn scaffold(day: u8, force: bool, dry_run: bool) -> Result<(), String> {
+    for file in plan(day, force).iter() {
+        if !dry_run {
+            file.write().map_err(|e| {
+                format!(
+                    "Failed to write {} file \"{}\": {}",
+                    file.kind, file.path, e
+                )
+            })?;
+        }
+        file.report(dry_run);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = temp_dir().join(format!("aoc_scaffold_test_{}", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_plan_module_file() {
+        let path = temp_path("module.rs");
+        assert_eq!(plan_module_file(&path, false), FileAction::Create);
+
+        fs::write(&path, "fn main() {}").unwrap();
+        assert!(matches!(
+            plan_module_file(&path, false),
+            FileAction::Skip(_)
+        ));
+        assert_eq!(plan_module_file(&path, true), FileAction::Overwrite);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_plan_data_file() {
+        let path = temp_path("input.txt");
+        assert_eq!(plan_data_file(&path), FileAction::Create);
+
+        fs::write(&path, "").unwrap();
+        assert_eq!(plan_data_file(&path), FileAction::Skip("already exists"));
+
+        fs::write(&path, "1000\n2000").unwrap();
+        assert_eq!(plan_data_file(&path), FileAction::Skip("not empty"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_overwrite_truncates() {
+        let path = temp_path("overwrite.rs");
+        fs::write(&path, "a much longer previous module").unwrap();
+
+        let mut file = ScaffoldFile {
+            kind: "module",
+            path: path.to_str().unwrap().to_string(),
+            contents: "new".to_string(),
+            action: FileAction::Overwrite,
+        };
+        file.write().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+
+        file.contents = "skipped".to_string();
+        file.action = FileAction::Skip("not empty");
+        file.write().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+
+        fs::remove_file(&path).unwrap();
+    }
+}